@@ -0,0 +1,199 @@
+//! Derive macros for `bytes_reader`, use them through its `derive` feature.
+
+use proc_macro::TokenStream;
+
+mod parse;
+mod read;
+mod write;
+
+/// Implements `bytes_reader::ByteRead` for a struct with named fields, reading the fields in
+/// declaration order.
+///
+/// Fields are configured with `#[br(...)]`:
+///
+/// - `be` / `le`: byte order of the field, also allowed on the struct to set it for every field.
+///   Otherwise the byte order passed to `ByteRead::byte_read` is used
+/// - `count = expr`: reads a `Vec` of `expr` items, `expr` can use the fields read before it
+/// - `align = n`: calls `ByteReader::align(n)` before reading the field
+/// - `c_str`: reads a `String` with `ByteSource::read_c_str`
+/// - `magic = expr`: fails with `ByteReaderError::InvalidMagic` unless the field equals `expr`.
+///   On the struct it takes a byte string, as in `magic = b"RIFF"`, which is read and checked
+///   before the first field
+#[proc_macro_derive(ByteRead, attributes(br))]
+pub fn derive_byte_read(input: TokenStream) -> TokenStream {
+    let code = match parse::parse_struct(input) {
+        Ok(s) => read::derive_byte_read(&s),
+        Err(message) => format!("compile_error!({message:?});"),
+    };
+
+    code.parse().unwrap()
+}
+
+/// Implements `bytes_reader::ByteWrite` for a struct with named fields, writing the fields in
+/// declaration order.
+///
+/// It takes the same `#[br(...)]` attributes as `ByteRead` and writes the layout it reads, so
+/// deriving both guarantees that reading back what was written gives the same value:
+///
+/// - `align = n` pads with `ByteWriter::align(n)`
+/// - `count = expr` fails with `ByteReaderError::CountMismatch` when `expr` does not match the
+///   length of the `Vec`. The fields named in `expr` are cloned, so they must be `Clone`
+/// - `c_str` writes the string followed by a NUL, and fails with `ByteReaderError::InteriorNul`
+///   if the string contains one
+/// - `magic = expr` on a field fails with `ByteReaderError::InvalidMagic` if the field does not
+///   equal `expr`, on the struct the byte string is written before the first field
+#[proc_macro_derive(ByteWrite, attributes(br))]
+pub fn derive_byte_write(input: TokenStream) -> TokenStream {
+    let code = match parse::parse_struct(input) {
+        Ok(s) => write::derive_byte_write(&s),
+        Err(message) => format!("compile_error!({message:?});"),
+    };
+
+    code.parse().unwrap()
+}
+
+/// Misuses of `#[br(...)]`, each example fails to derive with the error in its comment.
+///
+/// `count`, `align` and `c_str` only go on fields:
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: can only be used on fields
+/// #[derive(ByteRead)]
+/// #[br(count = 2)]
+/// struct Header {
+///     items: Vec<u8>,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: can only be used on fields
+/// #[derive(ByteRead)]
+/// #[br(align = 4)]
+/// struct Header {
+///     a: u8,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: can only be used on fields
+/// #[derive(ByteRead)]
+/// #[br(c_str)]
+/// struct Header {
+///     name: String,
+/// }
+/// ```
+///
+/// `be`, `le` and `c_str` take no value:
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: `be` does not take a value
+/// #[derive(ByteRead)]
+/// struct Header {
+///     #[br(be = 1)]
+///     a: u16,
+/// }
+/// ```
+///
+/// `count`, `align` and `magic` need one:
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: `count` expects a value
+/// #[derive(ByteRead)]
+/// struct Header {
+///     #[br(count)]
+///     items: Vec<u8>,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: `align` expects a value
+/// #[derive(ByteRead)]
+/// struct Header {
+///     #[br(align =)]
+///     a: u8,
+/// }
+/// ```
+///
+/// Unknown attributes are rejected:
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: unknown attribute `big`
+/// #[derive(ByteRead)]
+/// struct Header {
+///     #[br(big)]
+///     a: u16,
+/// }
+/// ```
+///
+/// The endianness can only be set once:
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: endianness is set more than once
+/// #[derive(ByteRead)]
+/// struct Header {
+///     #[br(be, le)]
+///     a: u16,
+/// }
+/// ```
+///
+/// A field cannot be both `c_str` and have a `count`:
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: cannot be both `c_str` and have a `count`
+/// #[derive(ByteRead)]
+/// struct Header {
+///     #[br(c_str, count = 2)]
+///     name: String,
+/// }
+/// ```
+///
+/// Only structs with named fields and no generics can be derived:
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: only structs can be derived
+/// #[derive(ByteRead)]
+/// enum Kind {
+///     A,
+///     B,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: generic structs are not supported
+/// #[derive(ByteRead)]
+/// struct Header<T> {
+///     a: T,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: only structs with named fields are supported
+/// #[derive(ByteRead)]
+/// struct Header(u8, u16);
+/// ```
+///
+/// `br` takes its attributes in parentheses:
+///
+/// ```compile_fail
+/// # use bytes_reader::ByteRead;
+/// // error: expected `#[br(...)]`
+/// #[derive(ByteRead)]
+/// struct Header {
+///     #[br]
+///     a: u8,
+/// }
+/// ```
+#[cfg(doctest)]
+mod attribute_errors {}
@@ -0,0 +1,223 @@
+use proc_macro::{Delimiter, Group, Spacing, TokenStream, TokenTree};
+
+pub(crate) struct Struct {
+    pub name: String,
+    pub attrs: Attrs,
+    pub fields: Vec<Field>,
+}
+
+pub(crate) struct Field {
+    pub name: String,
+    pub ty: String,
+    pub attrs: Attrs,
+}
+
+/// Everything set through `#[br(...)]`, expressions are kept as source text and pasted into the
+/// generated code as is
+#[derive(Default)]
+pub(crate) struct Attrs {
+    pub endian: Option<Endian>,
+    pub count: Option<String>,
+    pub align: Option<String>,
+    pub c_str: bool,
+    pub magic: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Endian {
+    Big,
+    Little,
+}
+
+impl Endian {
+    pub fn path(self) -> &'static str {
+        match self {
+            Self::Big => "::bytes_reader::Endian::Big",
+            Self::Little => "::bytes_reader::Endian::Little",
+        }
+    }
+}
+
+pub(crate) fn parse_struct(input: TokenStream) -> Result<Struct, String> {
+    let mut tokens = input.into_iter().peekable();
+    let mut attrs = Attrs::default();
+    loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '#' => match tokens.next() {
+                Some(TokenTree::Group(g)) => parse_attr(&g, &mut attrs)?,
+                _ => return Err("expected an attribute after `#`".into()),
+            },
+            Some(TokenTree::Ident(i)) if i.to_string() == "pub" => {
+                if let Some(TokenTree::Group(g)) = tokens.peek()
+                    && g.delimiter() == Delimiter::Parenthesis
+                {
+                    tokens.next();
+                }
+            }
+            Some(TokenTree::Ident(i)) if i.to_string() == "struct" => break,
+            _ => return Err("only structs can be derived".into()),
+        }
+    }
+
+    if attrs.count.is_some() || attrs.align.is_some() || attrs.c_str {
+        return Err("`count`, `align` and `c_str` can only be used on fields".into());
+    }
+
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(i)) => i.to_string(),
+        _ => return Err("expected the struct name".into()),
+    };
+
+    let fields = match tokens.next() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => parse_fields(g.stream())?,
+        Some(TokenTree::Punct(p)) if p.as_char() == '<' => {
+            return Err("generic structs are not supported".into());
+        }
+        _ => return Err("only structs with named fields are supported".into()),
+    };
+
+    Ok(Struct {
+        name,
+        attrs,
+        fields,
+    })
+}
+
+fn parse_fields(stream: TokenStream) -> Result<Vec<Field>, String> {
+    split_fields(stream)
+        .into_iter()
+        .filter(|tokens| !tokens.is_empty())
+        .map(parse_field)
+        .collect()
+}
+
+fn parse_field(tokens: Vec<TokenTree>) -> Result<Field, String> {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut attrs = Attrs::default();
+    let name = loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '#' => match tokens.next() {
+                Some(TokenTree::Group(g)) => parse_attr(&g, &mut attrs)?,
+                _ => return Err("expected an attribute after `#`".into()),
+            },
+            Some(TokenTree::Ident(i)) if i.to_string() == "pub" => {
+                if let Some(TokenTree::Group(g)) = tokens.peek()
+                    && g.delimiter() == Delimiter::Parenthesis
+                {
+                    tokens.next();
+                }
+            }
+            Some(TokenTree::Ident(i)) => break i.to_string(),
+            _ => return Err("expected a field name".into()),
+        }
+    };
+
+    match tokens.next() {
+        Some(TokenTree::Punct(p)) if p.as_char() == ':' => {}
+        _ => return Err(format!("expected `:` after field `{name}`")),
+    }
+
+    if attrs.c_str && attrs.count.is_some() {
+        return Err(format!(
+            "field `{name}` cannot be both `c_str` and have a `count`"
+        ));
+    }
+
+    Ok(Field {
+        name,
+        ty: tokens.collect::<TokenStream>().to_string(),
+        attrs,
+    })
+}
+
+/// Splits on top level commas, angle brackets are plain punctuation so their depth is tracked
+/// to keep `HashMap<K, V>` in one piece
+fn split_fields(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut fields = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut after_dash = false;
+    for token in stream {
+        if let TokenTree::Punct(p) = &token {
+            match p.as_char() {
+                '<' => depth += 1,
+                // `->` in function pointer types does not close anything
+                '>' if !after_dash => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    fields.push(Vec::new());
+                    after_dash = false;
+                    continue;
+                }
+                _ => {}
+            }
+            after_dash = p.as_char() == '-' && p.spacing() == Spacing::Joint;
+        } else {
+            after_dash = false;
+        }
+
+        fields.last_mut().unwrap().push(token);
+    }
+
+    fields
+}
+
+fn parse_attr(group: &Group, attrs: &mut Attrs) -> Result<(), String> {
+    let mut tokens = group.stream().into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(i)) if i.to_string() == "br" => {}
+        // Not ours, doc comments and the like
+        _ => return Ok(()),
+    }
+
+    let args = match tokens.next() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => g.stream(),
+        _ => return Err("expected `#[br(...)]`".into()),
+    };
+
+    for arg in split_fields(args) {
+        let mut arg = arg.into_iter();
+        let key = match arg.next() {
+            Some(TokenTree::Ident(i)) => i.to_string(),
+            None => continue,
+            _ => return Err("expected an attribute name inside `#[br(...)]`".into()),
+        };
+
+        let value = match arg.next() {
+            None => None,
+            Some(TokenTree::Punct(p)) if p.as_char() == '=' => {
+                let value = arg.collect::<TokenStream>().to_string();
+                if value.is_empty() {
+                    return Err(format!("`{key}` expects a value"));
+                }
+                Some(value)
+            }
+            _ => return Err(format!("expected `=` after `{key}`")),
+        };
+
+        match (key.as_str(), value) {
+            ("be", None) => set_endian(attrs, Endian::Big)?,
+            ("le", None) => set_endian(attrs, Endian::Little)?,
+            ("c_str", None) => attrs.c_str = true,
+            ("count", Some(value)) => attrs.count = Some(value),
+            ("align", Some(value)) => attrs.align = Some(value),
+            ("magic", Some(value)) => attrs.magic = Some(value),
+            ("be" | "le" | "c_str", Some(_)) => {
+                return Err(format!("`{key}` does not take a value"));
+            }
+            ("count" | "align" | "magic", None) => {
+                return Err(format!("`{key}` expects a value, as in `{key} = ...`"));
+            }
+            _ => return Err(format!("unknown attribute `{key}`")),
+        }
+    }
+
+    Ok(())
+}
+
+fn set_endian(attrs: &mut Attrs, endian: Endian) -> Result<(), String> {
+    if attrs.endian.is_some() {
+        return Err("endianness is set more than once".into());
+    }
+
+    attrs.endian = Some(endian);
+    Ok(())
+}
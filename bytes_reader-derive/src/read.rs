@@ -0,0 +1,75 @@
+use crate::parse::Struct;
+
+pub(crate) fn derive_byte_read(s: &Struct) -> String {
+    let mut body = String::new();
+    if let Some(endian) = s.attrs.endian {
+        body += &format!("let __endian = {};\n", endian.path());
+    }
+
+    if let Some(magic) = &s.attrs.magic {
+        body += &format!(
+            "let __at = __reader.get_position();
+            let __magic: &[u8] = {magic};
+            if __reader.read_block(__magic.len())? != __magic {{
+                return Err(::bytes_reader::ByteReaderError::InvalidMagic {{ at: __at }});
+            }}\n"
+        );
+    }
+
+    for field in &s.fields {
+        let (name, ty) = (&field.name, &field.ty);
+        let endian = field.attrs.endian.map_or("__endian", |e| e.path());
+        if let Some(align) = &field.attrs.align {
+            body += &format!("__reader.align({align})?;\n");
+        }
+
+        let value = if field.attrs.c_str {
+            "::bytes_reader::ByteSource::read_c_str(__reader)?".to_string()
+        } else if let Some(count) = &field.attrs.count {
+            format!(
+                "{{
+                    let mut __items = ::std::vec::Vec::new();
+                    for _ in 0..::bytes_reader::CountValue::count_value(&({count})) {{
+                        __items.push(::bytes_reader::ByteRead::byte_read(__reader, {endian})?);
+                    }}
+                    __items
+                }}"
+            )
+        } else {
+            format!("<{ty} as ::bytes_reader::ByteRead>::byte_read(__reader, {endian})?")
+        };
+
+        if field.attrs.magic.is_some() {
+            body += "let __at = __reader.get_position();\n";
+        }
+
+        body += &format!("let {name}: {ty} = {value};\n");
+        if let Some(magic) = &field.attrs.magic {
+            body += &format!(
+                "if {name} != {magic} {{
+                    return Err(::bytes_reader::ByteReaderError::InvalidMagic {{ at: __at }});
+                }}\n"
+            );
+        }
+    }
+
+    let fields = s
+        .fields
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "impl ::bytes_reader::ByteRead for {name} {{
+            fn byte_read(
+                __reader: &mut ::bytes_reader::ByteReader<'_>,
+                __endian: ::bytes_reader::Endian,
+            ) -> ::bytes_reader::Result<Self> {{
+                {body}
+                Ok(Self {{ {fields} }})
+            }}
+        }}",
+        name = s.name
+    )
+}
@@ -0,0 +1,88 @@
+use crate::parse::Struct;
+
+pub(crate) fn derive_byte_write(s: &Struct) -> String {
+    let mut body = String::new();
+    if let Some(endian) = s.attrs.endian {
+        body += &format!("let __endian = {};\n", endian.path());
+    }
+
+    if let Some(magic) = &s.attrs.magic {
+        body += &format!("__writer.write_block({magic});\n");
+    }
+
+    for field in &s.fields {
+        let name = &field.name;
+        let endian = field.attrs.endian.map_or("__endian", |e| e.path());
+        if let Some(align) = &field.attrs.align {
+            body += &format!("__writer.align({align})?;\n");
+        }
+
+        if let Some(magic) = &field.attrs.magic {
+            body += &format!(
+                "if self.{name} != {magic} {{
+                    return Err(::bytes_reader::ByteReaderError::InvalidMagic {{
+                        at: __writer.get_position(),
+                    }});
+                }}\n"
+            );
+        }
+
+        if field.attrs.c_str {
+            body += &format!("__writer.write_c_str(&self.{name})?;\n");
+        } else if let Some(count) = &field.attrs.count {
+            // The expression sees the fields it names by value, as when reading, so the same
+            // expression compiles for both
+            let bindings = s
+                .fields
+                .iter()
+                .filter(|f| mentions(count, &f.name))
+                .map(|f| {
+                    format!(
+                        "#[allow(unused_variables)]
+                        let {name} = ::std::clone::Clone::clone(&self.{name});\n",
+                        name = f.name
+                    )
+                })
+                .collect::<String>();
+            body += &format!(
+                "let __count = {{
+                    {bindings}
+                    ::bytes_reader::CountValue::count_value(&({count}))
+                }};
+                if __count != self.{name}.len() {{
+                    return Err(::bytes_reader::ByteReaderError::CountMismatch {{
+                        expected: __count,
+                        found: self.{name}.len(),
+                    }});
+                }}
+                for __item in self.{name}.iter() {{
+                    ::bytes_reader::ByteWrite::byte_write(__item, __writer, {endian})?;
+                }}\n"
+            );
+        } else {
+            body += &format!(
+                "::bytes_reader::ByteWrite::byte_write(&self.{name}, __writer, {endian})?;\n"
+            );
+        }
+    }
+
+    format!(
+        "impl ::bytes_reader::ByteWrite for {name} {{
+            fn byte_write(
+                &self,
+                __writer: &mut ::bytes_reader::ByteWriter,
+                __endian: ::bytes_reader::Endian,
+            ) -> ::bytes_reader::Result<()> {{
+                {body}
+                Ok(())
+            }}
+        }}",
+        name = s.name
+    )
+}
+
+/// Whether `name` appears as an identifier in the expression `expr`, raw ones included
+fn mentions(expr: &str, name: &str) -> bool {
+    expr.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '#'))
+        .any(|word| word == name)
+}
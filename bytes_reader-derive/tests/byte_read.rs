@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use bytes_reader::{ByteRead, ByteReader, ByteReaderError, Endian, Result};
+
+#[derive(ByteRead, Debug, PartialEq)]
+struct Header {
+    #[br(be)]
+    big: u16,
+    #[br(le)]
+    little: u16,
+    default: u32,
+    bytes: [u8; 3],
+    pairs: [(u8, i8); 2],
+}
+
+#[test]
+fn field_endianness_and_arrays() {
+    let bytes = [
+        0x01, 0x02, 0x01, 0x02, 0x01, 0x02, 0x03, 0x04, 7, 8, 9, 1, 0xff, 2, 0xfe,
+    ];
+    let expected = Header {
+        big: 0x0102,
+        little: 0x0201,
+        default: 0x0403_0201,
+        bytes: [7, 8, 9],
+        pairs: [(1, -1), (2, -2)],
+    };
+
+    let mut reader = ByteReader::with_endian(&bytes, Endian::Little);
+    assert_eq!(reader.read_value::<Header>().unwrap(), expected);
+    assert_eq!(reader.get_position(), bytes.len());
+
+    // The reader's endianness only applies to fields without their own
+    let mut reader = ByteReader::with_endian(&bytes, Endian::Big);
+    let header = reader.read_value::<Header>().unwrap();
+    assert_eq!((header.big, header.little), (0x0102, 0x0201));
+    assert_eq!(header.default, 0x0102_0304);
+}
+
+#[derive(ByteRead, Debug, PartialEq)]
+#[br(le)]
+struct Little {
+    a: u16,
+    #[br(be)]
+    b: u16,
+}
+
+#[test]
+fn struct_endianness() {
+    let bytes = [1, 0, 0, 1];
+    let mut reader = ByteReader::with_endian(&bytes, Endian::Big);
+    assert_eq!(
+        reader.read_value::<Little>().unwrap(),
+        Little { a: 1, b: 1 }
+    );
+}
+
+#[derive(ByteRead, Debug, PartialEq)]
+struct Counted {
+    len: u8,
+    #[br(count = len)]
+    items: Vec<u16>,
+    pairs: u16,
+    #[br(count = pairs as usize * 2, le)]
+    values: Vec<u16>,
+}
+
+#[test]
+fn counts_from_previous_fields() {
+    let bytes = [2, 0, 1, 0, 2, 0, 1, 3, 0, 4, 0];
+    let mut reader = ByteReader::new(&bytes);
+    assert_eq!(
+        reader.read_value::<Counted>().unwrap(),
+        Counted {
+            len: 2,
+            items: vec![1, 2],
+            pairs: 1,
+            values: vec![3, 4],
+        }
+    );
+}
+
+#[derive(ByteRead, Debug, PartialEq)]
+struct Aligned {
+    tag: u8,
+    #[br(align = 4)]
+    value: u32,
+    #[br(c_str)]
+    name: String,
+    #[br(align = 2)]
+    last: u8,
+}
+
+#[test]
+fn align_and_c_str() {
+    let bytes = [9, 0xcc, 0xcc, 0xcc, 0, 0, 0, 5, b'a', b'b', 0, 0xcc, 7];
+    let mut reader = ByteReader::new(&bytes);
+    assert_eq!(
+        reader.read_value::<Aligned>().unwrap(),
+        Aligned {
+            tag: 9,
+            value: 5,
+            name: "ab".into(),
+            last: 7,
+        }
+    );
+
+    // The padding must be there
+    let mut reader = ByteReader::new(&bytes[..2]);
+    assert!(matches!(
+        reader.read_value::<Aligned>(),
+        Err(ByteReaderError::UnexpectedEof { .. })
+    ));
+}
+
+#[derive(ByteRead, Debug, PartialEq)]
+#[br(magic = b"RIFF")]
+struct Riff {
+    size: u32,
+    #[br(magic = 0xfeed)]
+    tag: u16,
+}
+
+#[test]
+fn magic_values() {
+    let bytes = *b"RIFF\0\0\0\x04\xfe\xed";
+    let mut reader = ByteReader::new(&bytes);
+    assert_eq!(
+        reader.read_value::<Riff>().unwrap(),
+        Riff {
+            size: 4,
+            tag: 0xfeed
+        }
+    );
+
+    let bytes = *b"RIFX\0\0\0\x04\xfe\xed";
+    assert!(matches!(
+        ByteReader::new(&bytes).read_value::<Riff>(),
+        Err(ByteReaderError::InvalidMagic { at: 0 })
+    ));
+
+    let bytes = *b"RIFF\0\0\0\x04\xfe\xee";
+    assert!(matches!(
+        ByteReader::new(&bytes).read_value::<Riff>(),
+        Err(ByteReaderError::InvalidMagic { at: 8 })
+    ));
+}
+
+/// Reads nothing, stands in for field types the parser has to keep in one piece
+#[derive(Debug, PartialEq)]
+struct Skip<T, U = ()>(PhantomData<(T, U)>);
+
+impl<T, U> ByteRead for Skip<T, U> {
+    fn byte_read(_reader: &mut ByteReader<'_>, _endian: Endian) -> Result<Self> {
+        Ok(Self(PhantomData))
+    }
+}
+
+#[derive(ByteRead, Debug, PartialEq)]
+pub struct Tricky {
+    /// Doc comments are attributes too
+    pub first: u8,
+    pub(crate) function: Skip<fn() -> u8>,
+    map: Skip<HashMap<u8, u16>>,
+    function_args: Skip<fn(u8, u16) -> Vec<u8>>,
+    arrow_then_comma: Skip<fn() -> u8, u16>,
+    #[br(le)]
+    last: u16,
+}
+
+#[test]
+fn field_types_with_commas_and_arrows() {
+    let bytes = [1, 2, 0];
+    let tricky = ByteReader::new(&bytes).read_value::<Tricky>().unwrap();
+    assert_eq!((tricky.first, tricky.last), (1, 2));
+}
@@ -0,0 +1,170 @@
+use bytes_reader::{ByteRead, ByteReader, ByteReaderError, ByteWrite, ByteWriter, Endian};
+
+fn write<T: ByteWrite>(value: &T, endian: Endian) -> Vec<u8> {
+    let mut writer = ByteWriter::with_endian(endian);
+    writer.write_value(value).unwrap();
+    writer.into_inner().unwrap()
+}
+
+/// Writes `value`, reads it back and checks both give the same value and use every byte
+fn round_trip<T: ByteRead + ByteWrite + PartialEq + std::fmt::Debug>(
+    value: &T,
+    endian: Endian,
+) -> Vec<u8> {
+    let bytes = write(value, endian);
+    let mut reader = ByteReader::with_endian(&bytes, endian);
+    assert_eq!(&reader.read_value::<T>().unwrap(), value);
+    assert_eq!(reader.get_position(), bytes.len());
+    bytes
+}
+
+#[derive(ByteRead, ByteWrite, Debug, PartialEq)]
+#[br(magic = b"RIFF")]
+struct Riff {
+    size: u32,
+    #[br(magic = 0xfeed)]
+    tag: u16,
+}
+
+#[test]
+fn magic() {
+    let riff = Riff {
+        size: 4,
+        tag: 0xfeed,
+    };
+    assert_eq!(round_trip(&riff, Endian::Big), b"RIFF\0\0\0\x04\xfe\xed");
+
+    let bad = Riff { size: 4, tag: 1 };
+    let mut writer = ByteWriter::new();
+    assert!(matches!(
+        writer.write_value(&bad),
+        Err(ByteReaderError::InvalidMagic { at: 8 })
+    ));
+}
+
+#[derive(ByteRead, ByteWrite, Debug, PartialEq)]
+struct Counted {
+    len: u8,
+    #[br(count = len)]
+    items: Vec<u16>,
+    pairs: u16,
+    #[br(count = pairs as usize * 2, le)]
+    values: Vec<u16>,
+    r#type: u8,
+    #[br(count = r#type)]
+    raw: Vec<u8>,
+}
+
+#[test]
+fn count() {
+    let counted = Counted {
+        len: 2,
+        items: vec![1, 2],
+        pairs: 1,
+        values: vec![3, 4],
+        r#type: 1,
+        raw: vec![5],
+    };
+    assert_eq!(
+        round_trip(&counted, Endian::Big),
+        [2, 0, 1, 0, 2, 0, 1, 3, 0, 4, 0, 1, 5]
+    );
+
+    let mismatch = Counted {
+        pairs: 2,
+        ..counted
+    };
+    let mut writer = ByteWriter::new();
+    assert!(matches!(
+        writer.write_value(&mismatch),
+        Err(ByteReaderError::CountMismatch {
+            expected: 4,
+            found: 2
+        })
+    ));
+}
+
+#[derive(ByteRead, ByteWrite, Debug, PartialEq)]
+struct Aligned {
+    tag: u8,
+    #[br(align = 4)]
+    value: u32,
+    #[br(c_str)]
+    name: String,
+    #[br(align = 2)]
+    last: u8,
+}
+
+#[test]
+fn align_and_c_str() {
+    let aligned = Aligned {
+        tag: 9,
+        value: 5,
+        name: "ab".into(),
+        last: 7,
+    };
+    assert_eq!(
+        round_trip(&aligned, Endian::Big),
+        [9, 0, 0, 0, 0, 0, 0, 5, b'a', b'b', 0, 0, 7]
+    );
+
+    let nul = Aligned {
+        name: "a\0b".into(),
+        ..aligned
+    };
+    let mut writer = ByteWriter::new();
+    assert!(matches!(
+        writer.write_value(&nul),
+        Err(ByteReaderError::InteriorNul { at: 9 })
+    ));
+}
+
+#[derive(ByteRead, ByteWrite, Debug, PartialEq)]
+#[br(le)]
+struct Little {
+    a: u32,
+    #[br(be)]
+    b: u16,
+    c: [i16; 2],
+}
+
+#[test]
+fn struct_endianness() {
+    let little = Little {
+        a: 1,
+        b: 2,
+        c: [-1, 3],
+    };
+    // The writer's endianness does not matter, the struct sets its own
+    for endian in [Endian::Big, Endian::Little] {
+        assert_eq!(
+            round_trip(&little, endian),
+            [1, 0, 0, 0, 0, 2, 0xff, 0xff, 3, 0]
+        );
+    }
+}
+
+#[derive(ByteRead, ByteWrite, Debug, PartialEq)]
+struct Default {
+    a: u16,
+    nested: Little,
+    #[br(be)]
+    b: u16,
+}
+
+#[test]
+fn writer_endianness_applies_to_plain_fields() {
+    let value = Default {
+        a: 0x0102,
+        nested: Little {
+            a: 1,
+            b: 2,
+            c: [3, 4],
+        },
+        b: 0x0304,
+    };
+    let bytes = round_trip(&value, Endian::Little);
+    assert_eq!(bytes[..2], [2, 1]);
+    assert_eq!(bytes[bytes.len() - 2..], [3, 4]);
+    round_trip(&value, Endian::Big);
+}
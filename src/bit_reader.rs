@@ -0,0 +1,273 @@
+use crate::{ByteReader, ByteReaderError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Bits are taken from the most significant bit of each byte, as in H.264, FLAC and MPEG-TS
+    MsbFirst,
+    /// Bits are taken from the least significant bit of each byte, as in DEFLATE
+    LsbFirst,
+}
+
+/// Reads individual bits from a `ByteReader`, starting at its current position.
+///
+/// The underlying reader's cursor points at the byte holding the next unread bit, use
+/// `into_inner` to get it back positioned at the next whole byte.
+pub struct BitReader<'r, 'a> {
+    reader: &'r mut ByteReader<'a>,
+    order: BitOrder,
+    bit: u32,
+}
+
+impl<'r, 'a> BitReader<'r, 'a> {
+    pub fn new(reader: &'r mut ByteReader<'a>, order: BitOrder) -> Self {
+        Self {
+            reader,
+            order,
+            bit: 0,
+        }
+    }
+
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Reads `n` bits, the first bit read ends up as the most significant one for
+    /// `BitOrder::MsbFirst` and as the least significant one for `BitOrder::LsbFirst`.
+    ///
+    /// Panics if `n` is greater than 64.
+    pub fn read_bits(&mut self, n: u32) -> Result<u64> {
+        assert!(n <= 64, "cannot read {n} bits into a u64");
+        self.has_bits(n)?;
+
+        let mut value = 0u64;
+        let mut filled = 0;
+        while filled < n {
+            let byte = self.reader.buffer[self.reader.cursor] as u64;
+            let k = (n - filled).min(8 - self.bit);
+            let mask = (1u64 << k) - 1;
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let chunk = (byte >> (8 - self.bit - k)) & mask;
+                    value = (value << k) | chunk;
+                }
+                BitOrder::LsbFirst => {
+                    let chunk = (byte >> self.bit) & mask;
+                    value |= chunk << filled;
+                }
+            }
+
+            filled += k;
+            self.bit += k;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.reader.cursor += 1;
+            }
+        }
+
+        Ok(value)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Unsigned Exp-Golomb code, `ue(v)` in H.264
+    pub fn read_exp_golomb(&mut self) -> Result<u64> {
+        let (cursor, bit) = (self.reader.cursor, self.bit);
+        let value = self.read_exp_golomb_inner();
+        if value.is_err() {
+            self.reader.cursor = cursor;
+            self.bit = bit;
+        }
+
+        value
+    }
+
+    /// Signed Exp-Golomb code, `se(v)` in H.264
+    pub fn read_signed_exp_golomb(&mut self) -> Result<i64> {
+        let k = self.read_exp_golomb()?;
+        let magnitude = k.div_ceil(2) as i64;
+        match k % 2 {
+            1 => Ok(magnitude),
+            _ => Ok(-magnitude),
+        }
+    }
+
+    /// Skips the remaining bits of the current byte, if any
+    pub fn byte_align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.reader.cursor += 1;
+        }
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.bit == 0
+    }
+
+    /// Hands back the `ByteReader` positioned at the next whole byte
+    pub fn into_inner(mut self) -> &'r mut ByteReader<'a> {
+        self.byte_align();
+        self.reader
+    }
+
+    fn read_exp_golomb_inner(&mut self) -> Result<u64> {
+        let at = self.reader.cursor;
+        let mut leading_zeros = 0;
+        while !self.read_bool()? {
+            leading_zeros += 1;
+            if leading_zeros > 63 {
+                return Err(ByteReaderError::VarintOverflow { at });
+            }
+        }
+
+        let suffix = self.read_bits(leading_zeros)?;
+        Ok((1u64 << leading_zeros) - 1 + suffix)
+    }
+
+    fn has_bits(&self, n: u32) -> Result<()> {
+        let available = self.reader.len().saturating_sub(self.reader.cursor);
+        let needed = (self.bit + n).div_ceil(8) as usize;
+        if needed > available {
+            return Err(ByteReaderError::UnexpectedEof {
+                at: self.reader.cursor,
+                needed,
+                available,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> ByteReader<'a> {
+    /// Starts reading bits at the current position
+    pub fn bit_reader(&mut self, order: BitOrder) -> BitReader<'_, 'a> {
+        BitReader::new(self, order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ByteSource;
+
+    /// Packs a string of '0' and '1' MSB first, padding the last byte with zeros
+    fn bits(s: &str) -> Vec<u8> {
+        let bits: Vec<u8> = s.bytes().filter(|b| *b != b' ').map(|b| b - b'0').collect();
+        bits.chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, bit)| byte | bit << (7 - i))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn read_64_bits_msb_first() {
+        let bytes = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0xff];
+        let mut reader = ByteReader::new(&bytes);
+        let mut bits = reader.bit_reader(BitOrder::MsbFirst);
+        assert_eq!(bits.read_bits(64).unwrap(), 0x0123_4567_89ab_cdef);
+        assert_eq!(bits.read_bits(4).unwrap(), 0xf);
+        assert!(!bits.is_aligned());
+    }
+
+    #[test]
+    fn read_64_bits_lsb_first() {
+        let bytes = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+        let mut reader = ByteReader::new(&bytes);
+        let mut bits = reader.bit_reader(BitOrder::LsbFirst);
+        assert_eq!(bits.read_bits(64).unwrap(), 0xefcd_ab89_6745_2301);
+    }
+
+    #[test]
+    fn read_64_bits_unaligned() {
+        let bytes = [0xff; 9];
+        let mut reader = ByteReader::new(&bytes);
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            reader.reset();
+            let mut bits = reader.bit_reader(order);
+            bits.read_bits(3).unwrap();
+            assert_eq!(bits.read_bits(64).unwrap(), u64::MAX);
+            assert!(bits.read_bits(6).is_err());
+        }
+    }
+
+    #[test]
+    fn odd_widths_in_both_orders() {
+        // 0b1011_0010, 0b0110_1101
+        let bytes = [0xb2, 0x6d];
+        let mut reader = ByteReader::new(&bytes);
+        let mut bits = reader.bit_reader(BitOrder::MsbFirst);
+        assert_eq!(bits.read_bits(3).unwrap(), 0b101);
+        assert_eq!(bits.read_bits(7).unwrap(), 0b1001001);
+        assert_eq!(bits.read_bits(6).unwrap(), 0b101101);
+
+        let mut reader = ByteReader::new(&bytes);
+        let mut bits = reader.bit_reader(BitOrder::LsbFirst);
+        assert_eq!(bits.read_bits(3).unwrap(), 0b010);
+        assert_eq!(bits.read_bits(7).unwrap(), 0b0110110);
+        assert_eq!(bits.read_bits(6).unwrap(), 0b011011);
+    }
+
+    #[test]
+    fn exp_golomb_h264_vectors() {
+        let bytes = bits("1 010 011 00100 00101 00110 00111 0001000 000010100");
+        let mut reader = ByteReader::new(&bytes);
+        let mut bits = reader.bit_reader(BitOrder::MsbFirst);
+        for expected in 0..=7 {
+            assert_eq!(bits.read_exp_golomb().unwrap(), expected);
+        }
+        assert_eq!(bits.read_exp_golomb().unwrap(), 19);
+    }
+
+    #[test]
+    fn signed_exp_golomb_h264_vectors() {
+        let bytes = bits("1 010 011 00100 00101 00110 00111");
+        let mut reader = ByteReader::new(&bytes);
+        let mut bits = reader.bit_reader(BitOrder::MsbFirst);
+        for expected in [0, 1, -1, 2, -2, 3, -3] {
+            assert_eq!(bits.read_signed_exp_golomb().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn exp_golomb_rolls_back_on_eof() {
+        // One bit consumed, then 6 leading zeros whose suffix is cut off
+        let bytes = bits("1 0000001 1");
+        let mut reader = ByteReader::new(&bytes[..1]);
+        let mut bits = reader.bit_reader(BitOrder::MsbFirst);
+        assert!(bits.read_bool().unwrap());
+        assert!(matches!(
+            bits.read_exp_golomb(),
+            Err(ByteReaderError::UnexpectedEof { .. })
+        ));
+        assert_eq!(bits.read_bits(7).unwrap(), 0b0000001);
+        assert_eq!(bits.into_inner().get_position(), 1);
+    }
+
+    #[test]
+    fn exp_golomb_too_many_leading_zeros() {
+        let bytes = [0; 9];
+        let mut reader = ByteReader::new(&bytes);
+        let mut bits = reader.bit_reader(BitOrder::MsbFirst);
+        assert!(matches!(
+            bits.read_exp_golomb(),
+            Err(ByteReaderError::VarintOverflow { at: 0 })
+        ));
+        assert!(bits.is_aligned());
+    }
+
+    #[test]
+    fn into_inner_skips_to_next_byte() {
+        let bytes = [0xff, 0x12];
+        let mut reader = ByteReader::new(&bytes);
+        let mut bits = reader.bit_reader(BitOrder::MsbFirst);
+        bits.read_bits(1).unwrap();
+        let reader = bits.into_inner();
+        assert_eq!(reader.read_u8().unwrap(), 0x12);
+    }
+}
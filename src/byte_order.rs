@@ -0,0 +1,25 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    #[default]
+    Big,
+    Little,
+}
+
+/// Type level byte order, used to pick the endianness of `ByteSource::read` at compile time.
+pub trait ByteOrder {
+    const ENDIAN: Endian;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BigEndian {}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LittleEndian {}
+
+impl ByteOrder for BigEndian {
+    const ENDIAN: Endian = Endian::Big;
+}
+
+impl ByteOrder for LittleEndian {
+    const ENDIAN: Endian = Endian::Little;
+}
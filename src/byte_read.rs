@@ -0,0 +1,49 @@
+use crate::{ByteReader, ByteSource, Endian, FromBytes, Result};
+
+/// A value that can be read from a `ByteReader`, derive it with `#[derive(ByteRead)]` through
+/// the `derive` feature.
+///
+/// `endian` is the byte order to use for fields that do not specify their own.
+pub trait ByteRead: Sized {
+    fn byte_read(reader: &mut ByteReader<'_>, endian: Endian) -> Result<Self>;
+}
+
+impl<T: FromBytes> ByteRead for T {
+    fn byte_read(reader: &mut ByteReader<'_>, endian: Endian) -> Result<Self> {
+        reader.read_endian(endian)
+    }
+}
+
+impl<'a> ByteReader<'a> {
+    /// Reads a `ByteRead` value using the reader's endianness
+    pub fn read_value<T: ByteRead>(&mut self) -> Result<T> {
+        T::byte_read(self, self.endian)
+    }
+}
+
+/// Turns the value of a `#[br(count = ...)]` expression into a length, by value or by reference
+/// since derived writers only have references to the fields
+#[doc(hidden)]
+pub trait CountValue {
+    fn count_value(&self) -> usize;
+}
+
+macro_rules! impl_count_value {
+    ($($ty:ty),*) => {
+        $(
+            impl CountValue for $ty {
+                fn count_value(&self) -> usize {
+                    *self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_count_value!(u8, u16, u32, u64, usize);
+
+impl<T: CountValue + ?Sized> CountValue for &T {
+    fn count_value(&self) -> usize {
+        (**self).count_value()
+    }
+}
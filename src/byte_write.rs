@@ -0,0 +1,27 @@
+use crate::{BigEndian, ByteWriter, Endian, LittleEndian, Result, ToBytes};
+
+/// A value that can be written to a `ByteWriter`, the counterpart of `ByteRead`. Derive both
+/// with `#[derive(ByteRead, ByteWrite)]` through the `derive` feature so they share one layout.
+///
+/// `endian` is the byte order to use for fields that do not specify their own.
+pub trait ByteWrite {
+    fn byte_write(&self, writer: &mut ByteWriter, endian: Endian) -> Result<()>;
+}
+
+impl<T: ToBytes> ByteWrite for T {
+    fn byte_write(&self, writer: &mut ByteWriter, endian: Endian) -> Result<()> {
+        match endian {
+            Endian::Big => writer.write_ref::<T, BigEndian>(self),
+            Endian::Little => writer.write_ref::<T, LittleEndian>(self),
+        }
+
+        Ok(())
+    }
+}
+
+impl ByteWriter {
+    /// Writes a `ByteWrite` value using the writer's endianness
+    pub fn write_value<T: ByteWrite>(&mut self, value: &T) -> Result<()> {
+        value.byte_write(self, self.endian())
+    }
+}
@@ -0,0 +1,184 @@
+use std::{fmt, io};
+
+pub type Result<T> = std::result::Result<T, ByteReaderError>;
+
+/// Variants are added as new readers need them, so matches on it need a wildcard arm
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ByteReaderError {
+    /// Reading `needed` bytes at offset `at` would go past the end of the buffer, only `available`
+    /// bytes are left.
+    UnexpectedEof {
+        at: usize,
+        needed: usize,
+        available: usize,
+    },
+    /// `position` does not lie within a buffer of length `len`.
+    OutOfBounds { position: usize, len: usize },
+    /// Rewinding by `n` from `position` would put the cursor before the start of the buffer.
+    RewindPastStart { position: usize, n: usize },
+    /// Aligning to a multiple of `align` is not possible, as with 0.
+    InvalidAlignment { align: usize },
+    /// The variable-length integer starting at `at` has too many bytes or does not fit its
+    /// target type.
+    VarintOverflow { at: usize },
+    /// No NUL was found in the `searched` bytes of the C string starting at `at`.
+    UnterminatedCStr { at: usize, searched: usize },
+    /// The length prefix at offset `at` says `len` bytes, more than the `max` allowed.
+    PrefixTooLong { at: usize, len: u64, max: usize },
+    /// The string being read has invalid UTF-8 at offset `at`.
+    InvalidUtf8 { at: usize },
+    /// The UTF-16 string being read has an unpaired surrogate at offset `at`.
+    UnpairedSurrogate { at: usize },
+    /// A magic value checked by a derived `ByteRead` or `ByteWrite` at offset `at` does not match.
+    InvalidMagic { at: usize },
+    /// A derived `ByteWrite` was given `found` items for a field whose count says `expected`.
+    CountMismatch { expected: usize, found: usize },
+    /// A C string given to `ByteWriter::write_c_str` has a NUL that would land at offset `at`.
+    InteriorNul { at: usize },
+    /// A `ByteWriter` placeholder reserved at `position` was never filled.
+    UnfilledPlaceholder { position: usize },
+    /// The underlying source of a `StreamByteReader` failed.
+    Io(io::Error),
+    /// Any other failure, for `ByteRead` and `ByteWrite` impls that check more than the bytes
+    /// themselves. Only there with the `anyhow` feature, which lets `?` convert an
+    /// `anyhow::Error` into this variant.
+    #[cfg(feature = "anyhow")]
+    Other(anyhow::Error),
+}
+
+impl fmt::Display for ByteReaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof {
+                at,
+                needed,
+                available,
+            } => write!(
+                f,
+                "ByteReader has reached the end! cannot read {needed} bytes at offset {at}, only {available} bytes are available"
+            ),
+            Self::OutOfBounds { position, len } => write!(
+                f,
+                "Position: {position}, is outside the buffer bounds of length {len}"
+            ),
+            Self::RewindPastStart { position, n } => write!(
+                f,
+                "Rewinding by n: {n} from position {position}, will put the cursor position in negative value. If you want to reset cursor position use `reader.reset()` instead"
+            ),
+            Self::InvalidAlignment { align } => write!(f, "Cannot align to a multiple of {align}"),
+            Self::VarintOverflow { at } => write!(
+                f,
+                "Variable-length integer starting at offset {at} overflows its target type"
+            ),
+            Self::UnterminatedCStr { at, searched } => write!(
+                f,
+                "C string starting at offset {at} has no NUL terminator within {searched} bytes"
+            ),
+            Self::PrefixTooLong { at, len, max } => write!(
+                f,
+                "Length prefix at offset {at} says {len} bytes, more than the maximum of {max}"
+            ),
+            Self::InvalidUtf8 { at } => write!(f, "Invalid UTF-8 at offset {at}"),
+            Self::UnpairedSurrogate { at } => {
+                write!(f, "Unpaired UTF-16 surrogate at offset {at}")
+            }
+            Self::InvalidMagic { at } => write!(
+                f,
+                "Magic value at offset {at} does not match the expected one"
+            ),
+            Self::CountMismatch { expected, found } => write!(
+                f,
+                "Field has {found} items but its count says there should be {expected}"
+            ),
+            Self::InteriorNul { at } => {
+                write!(f, "C string has a NUL at offset {at} before its terminator")
+            }
+            Self::UnfilledPlaceholder { position } => write!(
+                f,
+                "Placeholder reserved at offset {position} was never filled"
+            ),
+            Self::Io(e) => write!(f, "Reading from the underlying source failed: {e}"),
+            #[cfg(feature = "anyhow")]
+            Self::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ByteReaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            #[cfg(feature = "anyhow")]
+            Self::Other(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ByteReaderError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl From<anyhow::Error> for ByteReaderError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Other(e)
+    }
+}
+
+/// Lets the `io::Read` and `io::Seek` impls of `ByteReader` report their errors, the original
+/// error is kept as the inner one
+impl From<ByteReaderError> for io::Error {
+    fn from(e: ByteReaderError) -> Self {
+        let kind = match e {
+            ByteReaderError::Io(e) => return e,
+            ByteReaderError::UnexpectedEof { .. } => io::ErrorKind::UnexpectedEof,
+            ByteReaderError::OutOfBounds { .. }
+            | ByteReaderError::RewindPastStart { .. }
+            | ByteReaderError::InvalidAlignment { .. } => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::InvalidData,
+        };
+
+        io::Error::new(kind, e)
+    }
+}
+
+#[cfg(all(test, feature = "anyhow"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anyhow_errors_convert_with_question_mark() {
+        use crate::{ByteRead, ByteReader, ByteSource, Endian};
+
+        #[derive(Debug)]
+        struct Port(u16);
+
+        fn check_port(port: u16) -> anyhow::Result<()> {
+            anyhow::ensure!(port != 0, "port 0 is reserved");
+            Ok(())
+        }
+
+        impl ByteRead for Port {
+            fn byte_read(reader: &mut ByteReader<'_>, endian: Endian) -> Result<Self> {
+                let port = reader.read_endian::<u16>(endian)?;
+                check_port(port)?;
+                Ok(Port(port))
+            }
+        }
+
+        assert_eq!(
+            ByteReader::new(&[0x1f, 0x90])
+                .read_value::<Port>()
+                .unwrap()
+                .0,
+            8080
+        );
+        let err = ByteReader::new(&[0, 0]).read_value::<Port>().unwrap_err();
+        assert!(matches!(err, ByteReaderError::Other(_)));
+        assert_eq!(err.to_string(), "port 0 is reserved");
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{ByteOrder, Endian};
+
+/// A value that can be decoded from a fixed amount of bytes.
+///
+/// Not implemented for `usize` and `isize`, their size depends on the platform.
+///
+/// Implement it for your own types to read them with `ByteSource::read`, composing the
+/// implementations of their fields.
+pub trait FromBytes: Sized {
+    /// Number of bytes consumed by `from_bytes`.
+    const SIZE: usize;
+
+    /// Decodes the value from the first `SIZE` bytes of `bytes`, panics if fewer bytes are given.
+    fn from_bytes<E: ByteOrder>(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_from_bytes {
+    ($($ty:ty),*) => {
+        $(
+            impl FromBytes for $ty {
+                const SIZE: usize = size_of::<$ty>();
+
+                fn from_bytes<E: ByteOrder>(bytes: &[u8]) -> Self {
+                    let bytes = bytes[..Self::SIZE].try_into().unwrap();
+                    match E::ENDIAN {
+                        Endian::Big => <$ty>::from_be_bytes(bytes),
+                        Endian::Little => <$ty>::from_le_bytes(bytes),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl<T: FromBytes, const N: usize> FromBytes for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn from_bytes<E: ByteOrder>(bytes: &[u8]) -> Self {
+        std::array::from_fn(|i| T::from_bytes::<E>(&bytes[i * T::SIZE..]))
+    }
+}
+
+macro_rules! impl_from_bytes_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: FromBytes),+> FromBytes for ($($name,)+) {
+            const SIZE: usize = 0 $(+ $name::SIZE)+;
+
+            // The offset is bumped once more after the last field
+            #[allow(unused_assignments)]
+            fn from_bytes<E: ByteOrder>(bytes: &[u8]) -> Self {
+                let mut offset = 0;
+                ($({
+                    let value = $name::from_bytes::<E>(&bytes[offset..]);
+                    offset += $name::SIZE;
+                    value
+                },)+)
+            }
+        }
+    };
+}
+
+impl_from_bytes_tuple!(T1);
+impl_from_bytes_tuple!(T1, T2);
+impl_from_bytes_tuple!(T1, T2, T3);
+impl_from_bytes_tuple!(T1, T2, T3, T4);
+impl_from_bytes_tuple!(T1, T2, T3, T4, T5);
+impl_from_bytes_tuple!(T1, T2, T3, T4, T5, T6);
+impl_from_bytes_tuple!(T1, T2, T3, T4, T5, T6, T7);
+impl_from_bytes_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
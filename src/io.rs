@@ -0,0 +1,146 @@
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+use crate::{ByteReader, ByteReaderError};
+
+/// Reads from the cursor on, so decoders taking `io::Read` pick up where the reader is and
+/// leave it after the bytes they used. `ByteSource::read` is the typed reader, call this one
+/// as `Read::read(&mut reader, buf)`.
+impl Read for ByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.len() - self.get_position());
+        buf[..n].copy_from_slice(self.read_block(n)?);
+        Ok(n)
+    }
+}
+
+impl BufRead for ByteReader<'_> {
+    /// Everything after the cursor, nothing is ever copied
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let remaining = self.len() - self.get_position();
+        Ok(self.peek_block(remaining)?)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.saturating_advance(amt);
+    }
+}
+
+/// Seeks move the same cursor as `set_position`, `advance` and `rewind`. Seeking past the end
+/// fails instead of being allowed as with `io::Cursor`. `ByteReader::rewind` takes a count,
+/// call this one as `Seek::rewind(&mut reader)`.
+impl Seek for ByteReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(n) => self.set_position(usize::try_from(n).unwrap_or(usize::MAX))?,
+            SeekFrom::Current(n) if n >= 0 => {
+                self.advance(usize::try_from(n).unwrap_or(usize::MAX))?
+            }
+            SeekFrom::Current(n) => {
+                self.rewind(usize::try_from(n.unsigned_abs()).unwrap_or(usize::MAX))?
+            }
+            SeekFrom::End(n) => {
+                let end = self.len();
+                let offset = usize::try_from(n.unsigned_abs()).unwrap_or(usize::MAX);
+                let position = if n >= 0 {
+                    end.saturating_add(offset)
+                } else {
+                    end.checked_sub(offset)
+                        .ok_or(ByteReaderError::RewindPastStart {
+                            position: end,
+                            n: offset,
+                        })?
+                };
+                self.set_position(position)?
+            }
+        }
+
+        Ok(self.get_position() as u64)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.get_position() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BYTES: &[u8] = b"ab\ncdef";
+
+    #[test]
+    fn seek_from_end() {
+        let mut reader = ByteReader::new(BYTES);
+        assert_eq!(reader.seek(SeekFrom::End(0)).unwrap(), 7);
+        assert_eq!(reader.seek(SeekFrom::End(-2)).unwrap(), 5);
+        assert_eq!(reader.seek(SeekFrom::End(-7)).unwrap(), 0);
+
+        reader.set_position(3).unwrap();
+        let past_end = reader.seek(SeekFrom::End(1)).unwrap_err();
+        assert_eq!(past_end.kind(), io::ErrorKind::InvalidInput);
+        let before_start = reader.seek(SeekFrom::End(-8)).unwrap_err();
+        assert_eq!(before_start.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.get_position(), 3);
+    }
+
+    #[test]
+    fn seek_from_current() {
+        let mut reader = ByteReader::new(BYTES);
+        reader.set_position(4).unwrap();
+        assert_eq!(reader.seek(SeekFrom::Current(-3)).unwrap(), 1);
+        assert_eq!(reader.seek(SeekFrom::Current(2)).unwrap(), 3);
+        assert!(reader.seek(SeekFrom::Current(-4)).is_err());
+        assert!(reader.seek(SeekFrom::Current(5)).is_err());
+        assert_eq!(reader.stream_position().unwrap(), 3);
+        Seek::rewind(&mut reader).unwrap();
+        assert_eq!(reader.get_position(), 0);
+    }
+
+    #[test]
+    fn read_returns_0_at_the_end() {
+        let mut reader = ByteReader::new(BYTES);
+        reader.set_position(5).unwrap();
+        let mut buf = [0; 4];
+        assert_eq!(Read::read(&mut reader, &mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"ef");
+        assert_eq!(Read::read(&mut reader, &mut buf).unwrap(), 0);
+        assert_eq!(reader.get_position(), 7);
+    }
+
+    #[test]
+    fn fill_buf_and_consume_move_the_cursor() {
+        let mut reader = ByteReader::new(BYTES);
+        reader.set_position(1).unwrap();
+        assert_eq!(reader.fill_buf().unwrap(), b"b\ncdef");
+        reader.consume(2);
+        assert_eq!(reader.get_position(), 3);
+        assert_eq!(reader.fill_buf().unwrap(), b"cdef");
+        reader.consume(10);
+        assert_eq!(reader.get_position(), 7);
+        assert!(reader.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_line_stops_after_the_newline() {
+        let mut reader = ByteReader::new(BYTES);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "ab\n");
+        assert_eq!(reader.get_position(), 3);
+
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "cdef");
+        assert_eq!(reader.get_position(), 7);
+    }
+
+    #[test]
+    fn copy_starts_at_the_cursor() {
+        let mut reader = ByteReader::new(BYTES);
+        reader.advance(3).unwrap();
+        let mut out = Vec::new();
+        assert_eq!(io::copy(&mut reader, &mut out).unwrap(), 4);
+        assert_eq!(out, b"cdef");
+        assert_eq!(reader.get_position(), 7);
+    }
+}
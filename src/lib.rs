@@ -1,145 +1,454 @@
-use anyhow::{Result, bail};
+macro_rules! endian_methods {
+    (peek: $($ty:ty => $be:ident, $le:ident, $default:ident;)*) => {
+        $(
+            pub fn $default(&self) -> Result<$ty> {
+                self.peek_endian::<$ty>(self.endian)
+            }
 
+            pub fn $be(&self) -> Result<$ty> {
+                self.peek::<$ty, BigEndian>()
+            }
+
+            pub fn $le(&self) -> Result<$ty> {
+                self.peek::<$ty, LittleEndian>()
+            }
+        )*
+    };
+    (at: $($ty:ty => $be:ident, $le:ident, $default:ident;)*) => {
+        $(
+            pub fn $default(&self, position: usize) -> Result<$ty> {
+                self.read_endian_at::<$ty>(position, self.endian)
+            }
+
+            pub fn $be(&self, position: usize) -> Result<$ty> {
+                self.read_at::<$ty, BigEndian>(position)
+            }
+
+            pub fn $le(&self, position: usize) -> Result<$ty> {
+                self.read_at::<$ty, LittleEndian>(position)
+            }
+        )*
+    };
+    (source read: $($ty:ty => $be:ident, $le:ident, $default:ident;)*) => {
+        $(
+            fn $default(&mut self) -> Result<$ty> {
+                self.read_endian::<$ty>(self.endian())
+            }
+
+            fn $be(&mut self) -> Result<$ty> {
+                self.read::<$ty, BigEndian>()
+            }
+
+            fn $le(&mut self) -> Result<$ty> {
+                self.read::<$ty, LittleEndian>()
+            }
+        )*
+    };
+    (source peek: $($ty:ty => $be:ident, $le:ident, $default:ident;)*) => {
+        $(
+            fn $default(&mut self) -> Result<$ty> {
+                self.peek_endian::<$ty>(self.endian())
+            }
+
+            fn $be(&mut self) -> Result<$ty> {
+                self.peek::<$ty, BigEndian>()
+            }
+
+            fn $le(&mut self) -> Result<$ty> {
+                self.peek::<$ty, LittleEndian>()
+            }
+        )*
+    };
+    (write: $($ty:ty => $be:ident, $le:ident, $default:ident;)*) => {
+        $(
+            pub fn $default(&mut self, value: $ty) {
+                self.write_endian::<$ty>(value, self.endian);
+            }
+
+            pub fn $be(&mut self, value: $ty) {
+                self.write::<$ty, BigEndian>(value);
+            }
+
+            pub fn $le(&mut self, value: $ty) {
+                self.write::<$ty, LittleEndian>(value);
+            }
+        )*
+    };
+    (reserve: $($ty:ty => $be:ident, $le:ident, $default:ident;)*) => {
+        $(
+            pub fn $default(&mut self) -> Placeholder<$ty> {
+                self.reserve_endian::<$ty>(self.endian)
+            }
+
+            pub fn $be(&mut self) -> Placeholder<$ty> {
+                self.reserve::<$ty, BigEndian>()
+            }
+
+            pub fn $le(&mut self) -> Placeholder<$ty> {
+                self.reserve::<$ty, LittleEndian>()
+            }
+        )*
+    };
+}
+
+mod bit_reader;
+mod byte_order;
+mod byte_read;
+mod byte_write;
+mod error;
+mod from_bytes;
+mod half;
+mod io;
+mod prefixed;
+mod source;
+mod stream;
+mod to_bytes;
+mod utf16;
+mod varint;
+mod writer;
+
+pub use bit_reader::{BitOrder, BitReader};
+pub use byte_order::{BigEndian, ByteOrder, Endian, LittleEndian};
+pub use byte_read::ByteRead;
+#[doc(hidden)]
+pub use byte_read::CountValue;
+pub use byte_write::ByteWrite;
+#[cfg(feature = "derive")]
+pub use bytes_reader_derive::{ByteRead, ByteWrite};
+pub use error::{ByteReaderError, Result};
+pub use from_bytes::FromBytes;
+pub use prefixed::{LengthPrefix, Varint};
+pub use source::{ByteSource, SeekableByteSource};
+pub use stream::StreamByteReader;
+pub use to_bytes::ToBytes;
+pub use utf16::Utf16Mode;
+pub use varint::{zigzag_decode_32, zigzag_decode_64};
+pub use writer::{ByteWriter, Placeholder};
+
+/// What the C string readers do when the input ends before a NUL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CStrMode {
+    /// Fail with `ByteReaderError::UnterminatedCStr`
+    RequireNul,
+    /// Take everything up to the end as the string
+    #[default]
+    AllowEof,
+}
+
+/// Reads from a buffer in memory with a tracked cursor. The typed readers (`read_u32`, ...) come
+/// from `ByteSource`, the methods here either borrow from the buffer for its whole lifetime or
+/// only need `&self`.
+#[derive(Clone)]
 pub struct ByteReader<'a> {
     cursor: usize,
     buffer: &'a [u8],
+    endian: Endian,
+    base: usize,
+    c_str_mode: CStrMode,
+    max_prefixed_len: usize,
+    utf16_mode: Utf16Mode,
 }
 
 impl<'a> ByteReader<'a> {
+    /// Creates a reader whose endian-less methods (`read_u32`, ...) read big endian
     pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_endian(bytes, Endian::Big)
+    }
+
+    pub fn with_endian(bytes: &'a [u8], endian: Endian) -> Self {
         Self {
             cursor: 0,
             buffer: bytes,
+            endian,
+            base: 0,
+            c_str_mode: CStrMode::AllowEof,
+            max_prefixed_len: usize::MAX,
+            utf16_mode: Utf16Mode::Lossy,
         }
     }
 
-    pub fn read_c_str(&mut self) -> Result<String> {
-        // We reading until null terminated or the end of buffer, it doesn't matter the length
-        self.has_space(1)?;
-        let len = self.buffer[self.cursor..]
-            .iter()
-            .position(|&b| b == b'\0')
-            .unwrap_or(self.buffer.len());
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    /// Changes the byte order used by `read_u16`, `read_u32`, ... for example after reading a BOM
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    pub fn c_str_mode(&self) -> CStrMode {
+        self.c_str_mode
+    }
 
-        let s = String::from_utf8_lossy(&self.buffer[self.cursor..self.cursor + len]).into_owned();
-        self.cursor = self.cursor + len + 1;
+    /// Changes whether `read_c_str` and friends accept a string cut off by the end of the buffer
+    pub fn set_c_str_mode(&mut self, mode: CStrMode) {
+        self.c_str_mode = mode;
+    }
+
+    /// Same as `read_c_str` but borrows the bytes, without the NUL, from the buffer
+    pub fn read_c_str_bytes(&mut self) -> Result<&'a [u8]> {
+        let mode = self.c_str_mode;
+        let (len, terminated) = source::c_str_len(self, usize::MAX, mode)?;
+        let bytes = self.get_block_at(self.cursor, len)?;
+        self.cursor += len + terminated as usize;
+        Ok(bytes)
+    }
+
+    /// Same as `read_c_str_bytes` but fails on invalid UTF-8 instead of replacing it, the cursor
+    /// is not advanced on failure
+    pub fn read_c_str_utf8(&mut self) -> Result<&'a str> {
+        let start = self.cursor;
+        let bytes = self.read_c_str_bytes()?;
+        to_str(bytes, start).inspect_err(|_| self.cursor = start)
+    }
+
+    /// Reads `len` bytes of UTF-8 borrowed from the buffer
+    pub fn read_str(&mut self, len: usize) -> Result<&'a str> {
+        self.has_space(len)?;
+        let s = to_str(&self.buffer[self.cursor..self.cursor + len], self.cursor)?;
+        self.cursor += len;
         Ok(s)
     }
 
-    pub fn read_u8(&mut self) -> Result<u8> {
-        let size = size_of::<u8>();
-        self.has_space(size)?;
-        let n = self.buffer[self.cursor];
-        self.cursor += size;
-        Ok(n)
-    }
-
-    pub fn read_u16_be(&mut self) -> Result<u16> {
-        let size = size_of::<u16>();
-        self.has_space(size)?;
-        let bytes = &self.buffer[self.cursor..self.cursor + size];
-        let n = u16::from_be_bytes([bytes[0], bytes[1]]);
-        self.cursor += size;
-        Ok(n)
-    }
-
-    pub fn read_u16_le(&mut self) -> Result<u16> {
-        let size = size_of::<u16>();
-        self.has_space(size)?;
-        let bytes = &self.buffer[self.cursor..self.cursor + size];
-        let n = u16::from_le_bytes([bytes[0], bytes[1]]);
-        self.cursor += size;
-        Ok(n)
-    }
-
-    pub fn read_u32_be(&mut self) -> Result<u32> {
-        let size = size_of::<u32>();
-        self.has_space(size)?;
-        let bytes = &self.buffer[self.cursor..self.cursor + size];
-        let n = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
-        self.cursor += size;
-        Ok(n)
-    }
-
-    pub fn read_u32_le(&mut self) -> Result<u32> {
-        let size = size_of::<u32>();
-        self.has_space(size)?;
-        let bytes = &self.buffer[self.cursor..self.cursor + size];
-        let n = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
-        self.cursor += size;
-        Ok(n)
-    }
-
-    pub fn read_u64_be(&mut self) -> Result<u64> {
-        let size = size_of::<u64>();
-        self.has_space(size)?;
-        let bytes = &self.buffer[self.cursor..self.cursor + size];
-        let n = u64::from_be_bytes([
-            bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
-        ]);
-        self.cursor += size;
-        Ok(n)
-    }
-
-    pub fn read_u64_le(&mut self) -> Result<u64> {
-        let size = size_of::<u64>();
-        self.has_space(size)?;
-        let bytes = &self.buffer[self.cursor..self.cursor + size];
-        let n = u64::from_le_bytes([
-            bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
-        ]);
-        self.cursor += size;
-        Ok(n)
-    }
-
-    pub fn advance(&mut self, n: usize) {
+    /// Same as `read_c_str` but does not advance the cursor
+    pub fn peek_c_str(&self) -> Result<String> {
+        self.clone().read_c_str()
+    }
+
+    /// Same as `read` but does not advance the cursor
+    pub fn peek<T: FromBytes, E: ByteOrder>(&self) -> Result<T> {
+        self.has_space(T::SIZE)?;
+        Ok(T::from_bytes::<E>(
+            &self.buffer[self.cursor..self.cursor + T::SIZE],
+        ))
+    }
+
+    pub fn peek_endian<T: FromBytes>(&self, endian: Endian) -> Result<T> {
+        match endian {
+            Endian::Big => self.peek::<T, BigEndian>(),
+            Endian::Little => self.peek::<T, LittleEndian>(),
+        }
+    }
+
+    pub fn peek_u8(&self) -> Result<u8> {
+        self.peek::<u8, BigEndian>()
+    }
+
+    pub fn peek_i8(&self) -> Result<i8> {
+        self.peek::<i8, BigEndian>()
+    }
+
+    endian_methods! {
+        peek:
+        u16 => peek_u16_be, peek_u16_le, peek_u16;
+        i16 => peek_i16_be, peek_i16_le, peek_i16;
+        u32 => peek_u32_be, peek_u32_le, peek_u32;
+        i32 => peek_i32_be, peek_i32_le, peek_i32;
+        u64 => peek_u64_be, peek_u64_le, peek_u64;
+        i64 => peek_i64_be, peek_i64_le, peek_i64;
+        u128 => peek_u128_be, peek_u128_le, peek_u128;
+        i128 => peek_i128_be, peek_i128_le, peek_i128;
+        f32 => peek_f32_be, peek_f32_le, peek_f32;
+        f64 => peek_f64_be, peek_f64_le, peek_f64;
+    }
+
+    pub fn peek_f16(&self) -> Result<f32> {
+        self.peek_u16().map(half::f16_to_f32)
+    }
+
+    pub fn peek_f16_be(&self) -> Result<f32> {
+        self.peek_u16_be().map(half::f16_to_f32)
+    }
+
+    pub fn peek_f16_le(&self) -> Result<f32> {
+        self.peek_u16_le().map(half::f16_to_f32)
+    }
+
+    pub fn peek_bf16(&self) -> Result<f32> {
+        self.peek_u16().map(half::bf16_to_f32)
+    }
+
+    pub fn peek_bf16_be(&self) -> Result<f32> {
+        self.peek_u16_be().map(half::bf16_to_f32)
+    }
+
+    pub fn peek_bf16_le(&self) -> Result<f32> {
+        self.peek_u16_le().map(half::bf16_to_f32)
+    }
+
+    /// Same as `read_c_str` but reads at `position` and does not touch the cursor
+    pub fn read_c_str_at(&self, position: usize) -> Result<String> {
+        let mut reader = self.clone();
+        reader.cursor = position;
+        reader.read_c_str()
+    }
+
+    /// Same as `read` but reads at `position` and does not touch the cursor
+    pub fn read_at<T: FromBytes, E: ByteOrder>(&self, position: usize) -> Result<T> {
+        let bytes = self.get_block_at(position, T::SIZE)?;
+        Ok(T::from_bytes::<E>(bytes))
+    }
+
+    pub fn read_endian_at<T: FromBytes>(&self, position: usize, endian: Endian) -> Result<T> {
+        match endian {
+            Endian::Big => self.read_at::<T, BigEndian>(position),
+            Endian::Little => self.read_at::<T, LittleEndian>(position),
+        }
+    }
+
+    pub fn read_u8_at(&self, position: usize) -> Result<u8> {
+        self.read_at::<u8, BigEndian>(position)
+    }
+
+    pub fn read_i8_at(&self, position: usize) -> Result<i8> {
+        self.read_at::<i8, BigEndian>(position)
+    }
+
+    endian_methods! {
+        at:
+        u16 => read_u16_be_at, read_u16_le_at, read_u16_at;
+        i16 => read_i16_be_at, read_i16_le_at, read_i16_at;
+        u32 => read_u32_be_at, read_u32_le_at, read_u32_at;
+        i32 => read_i32_be_at, read_i32_le_at, read_i32_at;
+        u64 => read_u64_be_at, read_u64_le_at, read_u64_at;
+        i64 => read_i64_be_at, read_i64_le_at, read_i64_at;
+        u128 => read_u128_be_at, read_u128_le_at, read_u128_at;
+        i128 => read_i128_be_at, read_i128_le_at, read_i128_at;
+        f32 => read_f32_be_at, read_f32_le_at, read_f32_at;
+        f64 => read_f64_be_at, read_f64_le_at, read_f64_at;
+    }
+
+    pub fn read_f16_at(&self, position: usize) -> Result<f32> {
+        self.read_u16_at(position).map(half::f16_to_f32)
+    }
+
+    pub fn read_f16_be_at(&self, position: usize) -> Result<f32> {
+        self.read_u16_be_at(position).map(half::f16_to_f32)
+    }
+
+    pub fn read_f16_le_at(&self, position: usize) -> Result<f32> {
+        self.read_u16_le_at(position).map(half::f16_to_f32)
+    }
+
+    pub fn read_bf16_at(&self, position: usize) -> Result<f32> {
+        self.read_u16_at(position).map(half::bf16_to_f32)
+    }
+
+    pub fn read_bf16_be_at(&self, position: usize) -> Result<f32> {
+        self.read_u16_be_at(position).map(half::bf16_to_f32)
+    }
+
+    pub fn read_bf16_le_at(&self, position: usize) -> Result<f32> {
+        self.read_u16_le_at(position).map(half::bf16_to_f32)
+    }
+
+    pub fn advance(&mut self, n: usize) -> Result<()> {
+        self.has_space(n)?;
         self.cursor += n;
+        Ok(())
     }
 
-    pub fn align(&mut self, n: usize) {
-        let offset_before_align = self.cursor;
-        let remain = offset_before_align % n;
-        let offset_after_align = match remain {
-            0 => offset_before_align,
-            _ => offset_before_align - remain + n,
-        };
-        self.cursor = offset_after_align;
+    /// Same as `advance` but stops at the end of the buffer instead of failing
+    pub fn saturating_advance(&mut self, n: usize) {
+        self.cursor = self.cursor.saturating_add(n).min(self.buffer.len());
+    }
+
+    /// Moves the cursor forward to the next multiple of `n`, fails if `n` is 0 or the aligned
+    /// position is past the end of the buffer
+    pub fn align(&mut self, n: usize) -> Result<()> {
+        let padding = self.align_padding(n)?;
+        self.advance(padding)
+    }
+
+    /// Same as `align` but stops at the end of the buffer instead of failing, `n` of 0 does nothing
+    pub fn saturating_align(&mut self, n: usize) {
+        if let Ok(padding) = self.align_padding(n) {
+            self.saturating_advance(padding);
+        }
     }
 
     pub fn len(&self) -> usize {
         self.buffer.len()
     }
 
-    pub fn read_block(&mut self, n: usize) -> Result<&[u8]> {
-        self.has_space(n)?;
-        let v = &self.buffer[self.cursor..self.cursor + n];
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Offset of this reader's first byte within the buffer of the outermost reader, non zero
+    /// only for readers made by `sub_reader` and `window`
+    pub fn base_offset(&self) -> usize {
+        self.base
+    }
+
+    /// Splits off the next `len` bytes into a reader of their own and advances past them, the
+    /// child cannot read outside of those bytes and its positions start at 0
+    pub fn sub_reader(&mut self, len: usize) -> Result<ByteReader<'a>> {
+        let reader = self.window(self.cursor, len)?;
+        self.cursor += len;
+        Ok(reader)
+    }
+
+    /// Same as `sub_reader` but starts at `position` and does not advance the cursor
+    pub fn window(&self, position: usize, len: usize) -> Result<ByteReader<'a>> {
+        Ok(ByteReader {
+            cursor: 0,
+            buffer: self.get_block_at(position, len)?,
+            endian: self.endian,
+            base: self.base + position,
+            c_str_mode: self.c_str_mode,
+            max_prefixed_len: self.max_prefixed_len,
+            utf16_mode: self.utf16_mode,
+        })
+    }
+
+    /// The block borrows from the buffer rather than the reader, so it can be kept while reading on
+    pub fn read_block(&mut self, n: usize) -> Result<&'a [u8]> {
+        let v = self.get_block_at(self.cursor, n)?;
         self.cursor += n;
         Ok(v)
     }
 
+    /// Same as `read_block` but does not advance the cursor
+    pub fn peek_block(&self, n: usize) -> Result<&'a [u8]> {
+        self.has_space(n)?;
+        Ok(&self.buffer[self.cursor..self.cursor + n])
+    }
+
     /// Does not advance the cursor
-    pub fn get_block_at(&self, position: usize, length: usize) -> Result<&[u8]> {
-        if position + length > self.buffer.len() {
-            bail!(
-                "Position: {position}, and Length: {length}, exceeds the buffer size {}",
-                self.buffer.len()
-            );
+    pub fn get_block_at(&self, position: usize, length: usize) -> Result<&'a [u8]> {
+        // Offsets usually come from the data itself, so guard against overflowing them
+        if position
+            .checked_add(length)
+            .is_none_or(|end| end > self.buffer.len())
+        {
+            return Err(ByteReaderError::UnexpectedEof {
+                at: position,
+                needed: length,
+                available: self.buffer.len().saturating_sub(position),
+            });
         }
 
         Ok(&self.buffer[position..position + length])
     }
 
     pub fn rewind(&mut self, n: usize) -> Result<()> {
-        if n < self.cursor {
-            bail!(
-                "Rewinding by n: {n}, will put the cursor position in negative value. If you want to reset cursor position use `reader.reset()` instead"
-            );
+        if n > self.cursor {
+            return Err(ByteReaderError::RewindPastStart {
+                position: self.cursor,
+                n,
+            });
         }
 
         self.cursor -= n;
         Ok(())
     }
 
+    /// Same as `rewind` but stops at the start of the buffer instead of failing
+    pub fn saturating_rewind(&mut self, n: usize) {
+        self.cursor = self.cursor.saturating_sub(n);
+    }
+
     pub fn reset(&mut self) {
         self.cursor = 0;
     }
@@ -148,25 +457,325 @@ impl<'a> ByteReader<'a> {
         self.cursor
     }
 
+    /// `n` can be `len()`, the position after the last byte
     pub fn set_position(&mut self, n: usize) -> Result<()> {
-        if n >= self.buffer.len() {
-            bail!(
-                "Position n: {n}, is outside the buffer bounds of length {}",
-                self.buffer.len()
-            );
+        if n > self.buffer.len() {
+            return Err(ByteReaderError::OutOfBounds {
+                position: n,
+                len: self.buffer.len(),
+            });
         }
 
         self.cursor = n;
         Ok(())
     }
 
+    fn align_padding(&self, n: usize) -> Result<usize> {
+        if n == 0 {
+            return Err(ByteReaderError::InvalidAlignment { align: n });
+        }
+
+        Ok(self.cursor.next_multiple_of(n) - self.cursor)
+    }
+
     fn has_space(&self, length: usize) -> Result<()> {
-        if self.cursor + length > self.buffer.len() {
-            bail!(
-                "ByteReader has reached the end! cannot read anymore bytes, consider rewinding if you want to re-read some bytes"
-            );
+        if length > self.buffer.len().saturating_sub(self.cursor) {
+            return Err(ByteReaderError::UnexpectedEof {
+                at: self.cursor,
+                needed: length,
+                available: self.buffer.len().saturating_sub(self.cursor),
+            });
         }
 
         Ok(())
     }
 }
+
+/// `at` is the offset of `bytes` in the buffer, used to locate the invalid byte
+fn to_str(bytes: &[u8], at: usize) -> Result<&str> {
+    str::from_utf8(bytes).map_err(|e| ByteReaderError::InvalidUtf8 {
+        at: at + e.valid_up_to(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn c_str_without_nul_at_non_zero_cursor() {
+        let bytes = b"ab\0cdef";
+        for start in 3..bytes.len() {
+            let mut reader = ByteReader::new(bytes);
+            reader.set_position(start).unwrap();
+            assert_eq!(reader.peek_c_str().unwrap().as_bytes(), &bytes[start..]);
+            assert_eq!(reader.read_c_str().unwrap().as_bytes(), &bytes[start..]);
+            assert_eq!(reader.get_position(), bytes.len());
+
+            let mut reader = ByteReader::new(bytes);
+            reader.set_position(start).unwrap();
+            reader.set_c_str_mode(CStrMode::RequireNul);
+            let searched = bytes.len() - start;
+            assert!(matches!(
+                reader.read_c_str(),
+                Err(ByteReaderError::UnterminatedCStr { at, searched: s }) if at == start && s == searched
+            ));
+            assert!(reader.read_c_str_bytes().is_err());
+            assert!(reader.read_c_str_utf8().is_err());
+            assert_eq!(reader.get_position(), start);
+        }
+    }
+
+    #[test]
+    fn c_str_with_nul_as_last_byte() {
+        let bytes = b"x\0abc\0";
+        for mode in [CStrMode::AllowEof, CStrMode::RequireNul] {
+            let mut reader = ByteReader::new(bytes);
+            reader.set_c_str_mode(mode);
+            reader.advance(2).unwrap();
+            assert_eq!(reader.read_c_str().unwrap(), "abc");
+            assert_eq!(reader.get_position(), bytes.len());
+        }
+        assert_eq!(ByteReader::new(bytes).read_c_str_at(2).unwrap(), "abc");
+    }
+
+    #[test]
+    fn c_str_at_end_of_buffer() {
+        let mut reader = ByteReader::new(b"ab\0");
+        reader.advance(3).unwrap();
+        assert!(matches!(
+            reader.read_c_str(),
+            Err(ByteReaderError::UnexpectedEof { at: 3, .. })
+        ));
+        assert!(reader.read_c_str_at(10).is_err());
+    }
+
+    #[test]
+    fn c_str_empty() {
+        let mut reader = ByteReader::new(b"\0\0a");
+        assert_eq!(reader.read_c_str().unwrap(), "");
+        assert_eq!(reader.read_c_str_bytes().unwrap(), b"");
+        assert_eq!(reader.get_position(), 2);
+    }
+
+    #[test]
+    fn c_str_max_limit() {
+        let bytes = b"-abcd\0";
+        // NUL at index limit - 1 of the string is within the limit
+        let mut reader = ByteReader::new(bytes);
+        reader.advance(1).unwrap();
+        assert_eq!(reader.read_c_str_max(5).unwrap(), "abcd");
+        assert_eq!(reader.get_position(), bytes.len());
+
+        // NUL at index limit is not
+        let mut reader = ByteReader::new(bytes);
+        reader.advance(1).unwrap();
+        assert!(matches!(
+            reader.read_c_str_max(4),
+            Err(ByteReaderError::UnterminatedCStr { at: 1, searched: 4 })
+        ));
+        assert_eq!(reader.get_position(), 1);
+
+        // A NUL is required even if the limit reaches the end of the buffer
+        let mut reader = ByteReader::new(b"-abcd");
+        reader.advance(1).unwrap();
+        assert!(matches!(
+            reader.read_c_str_max(10),
+            Err(ByteReaderError::UnterminatedCStr { at: 1, searched: 4 })
+        ));
+    }
+
+    #[test]
+    fn c_str_utf8_is_strict() {
+        let mut reader = ByteReader::new(b"a\xffb\0");
+        assert!(matches!(
+            reader.read_c_str_utf8(),
+            Err(ByteReaderError::InvalidUtf8 { at: 1 })
+        ));
+        assert_eq!(reader.get_position(), 0);
+        assert_eq!(reader.read_c_str().unwrap(), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn cursor_never_passes_len() {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next = |max: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % max as u64) as usize
+        };
+
+        for len in 0..24 {
+            let bytes = vec![0; len];
+            let mut reader = ByteReader::new(&bytes);
+            for _ in 0..2000 {
+                let n = match next(8) {
+                    0 => usize::MAX - next(2),
+                    _ => next(len + 6),
+                };
+                let before = reader.get_position();
+                let ok = match next(7) {
+                    0 => reader.advance(n).is_ok(),
+                    1 => reader.align(n).is_ok(),
+                    2 => reader.rewind(n).is_ok(),
+                    3 => reader.set_position(n).is_ok(),
+                    4 => {
+                        reader.saturating_advance(n);
+                        true
+                    }
+                    5 => {
+                        reader.saturating_align(n);
+                        true
+                    }
+                    _ => {
+                        reader.saturating_rewind(n);
+                        true
+                    }
+                };
+
+                assert!(reader.get_position() <= reader.len());
+                if !ok {
+                    assert_eq!(reader.get_position(), before);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn advance_is_checked() {
+        let mut reader = ByteReader::new(&[0; 4]);
+        reader.advance(4).unwrap();
+        assert_eq!(reader.get_position(), 4);
+        assert!(matches!(
+            reader.advance(1),
+            Err(ByteReaderError::UnexpectedEof {
+                at: 4,
+                needed: 1,
+                available: 0
+            })
+        ));
+        reader.reset();
+        assert!(reader.advance(usize::MAX).is_err());
+        assert_eq!(reader.get_position(), 0);
+        reader.saturating_advance(usize::MAX);
+        assert_eq!(reader.get_position(), 4);
+    }
+
+    #[test]
+    fn align_zero_is_an_error() {
+        let mut reader = ByteReader::new(&[0; 8]);
+        reader.advance(3).unwrap();
+        assert!(matches!(
+            reader.align(0),
+            Err(ByteReaderError::InvalidAlignment { align: 0 })
+        ));
+        reader.saturating_align(0);
+        assert_eq!(reader.get_position(), 3);
+
+        reader.align(4).unwrap();
+        assert_eq!(reader.get_position(), 4);
+        reader.align(4).unwrap();
+        assert_eq!(reader.get_position(), 4);
+        reader.advance(1).unwrap();
+        // Aligned position 16 is past the end
+        assert!(reader.align(16).is_err());
+        assert_eq!(reader.get_position(), 5);
+        reader.saturating_align(16);
+        assert_eq!(reader.get_position(), 8);
+    }
+
+    #[test]
+    fn rewind_guard() {
+        let mut reader = ByteReader::new(&[0; 8]);
+        reader.advance(5).unwrap();
+        reader.rewind(2).unwrap();
+        assert_eq!(reader.get_position(), 3);
+        // Back to exactly the start is allowed
+        reader.rewind(3).unwrap();
+        assert_eq!(reader.get_position(), 0);
+
+        reader.advance(2).unwrap();
+        assert!(matches!(
+            reader.rewind(3),
+            Err(ByteReaderError::RewindPastStart { position: 2, n: 3 })
+        ));
+        assert_eq!(reader.get_position(), 2);
+        reader.saturating_rewind(3);
+        assert_eq!(reader.get_position(), 0);
+    }
+
+    #[test]
+    fn set_position_allows_end() {
+        let mut reader = ByteReader::new(&[1, 2]);
+        reader.set_position(2).unwrap();
+        assert!(reader.read_u8().is_err());
+        assert!(matches!(
+            reader.set_position(3),
+            Err(ByteReaderError::OutOfBounds {
+                position: 3,
+                len: 2
+            })
+        ));
+        assert_eq!(reader.get_position(), 2);
+    }
+
+    #[test]
+    fn sub_reader_advances_the_parent() {
+        let bytes = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut parent = ByteReader::new(&bytes);
+        parent.advance(1).unwrap();
+        let mut child = parent.sub_reader(3).unwrap();
+        assert_eq!(parent.get_position(), 4);
+        assert_eq!(child.get_position(), 0);
+        assert_eq!(child.len(), 3);
+        assert_eq!(child.base_offset(), 1);
+        assert_eq!(child.read_block(3).unwrap(), [2, 3, 4]);
+        // The child ends where its bytes do, not where the parent's do
+        assert!(matches!(
+            child.read_u8(),
+            Err(ByteReaderError::UnexpectedEof {
+                at: 3,
+                needed: 1,
+                available: 0
+            })
+        ));
+        assert!(child.set_position(4).is_err());
+        assert_eq!(parent.read_u8().unwrap(), 5);
+
+        assert!(parent.sub_reader(4).is_err());
+        assert_eq!(parent.get_position(), 5);
+    }
+
+    #[test]
+    fn window_does_not_advance() {
+        let bytes = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut parent = ByteReader::new(&bytes);
+        parent.advance(1).unwrap();
+        let mut child = parent.window(4, 2).unwrap();
+        assert_eq!(parent.get_position(), 1);
+        assert_eq!(child.get_position(), 0);
+        assert_eq!(child.read_u16().unwrap(), 0x0506);
+        assert!(child.read_u8().is_err());
+
+        assert!(parent.window(7, 2).is_err());
+        assert!(matches!(
+            parent.window(usize::MAX, 2),
+            Err(ByteReaderError::UnexpectedEof { .. })
+        ));
+    }
+
+    #[test]
+    fn nested_base_offsets_add_up() {
+        let bytes: Vec<u8> = (0..16).collect();
+        let mut parent = ByteReader::new(&bytes);
+        parent.advance(2).unwrap();
+        let mut child = parent.sub_reader(10).unwrap();
+        child.advance(3).unwrap();
+        let mut grandchild = child.sub_reader(4).unwrap();
+        assert_eq!(child.base_offset(), 2);
+        assert_eq!(grandchild.base_offset(), 5);
+        assert_eq!(grandchild.read_u8().unwrap(), 5);
+        assert_eq!(child.window(1, 2).unwrap().base_offset(), 3);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{ByteReader, ByteReaderError, ByteSource, Endian, FromBytes, Result, to_str, varint};
+
+/// The length in front of a string or blob read by `read_prefixed_bytes`, implemented for `u8`,
+/// `u16`, `u32`, `u64` and `Varint`
+pub trait LengthPrefix {
+    /// Returns the length and the size of the prefix in bytes, without advancing
+    fn peek_len<S: ByteSource + ?Sized>(source: &mut S, endian: Endian) -> Result<(u64, usize)>;
+}
+
+macro_rules! impl_length_prefix {
+    ($($ty:ty),*) => {
+        $(
+            impl LengthPrefix for $ty {
+                fn peek_len<S: ByteSource + ?Sized>(
+                    source: &mut S,
+                    endian: Endian,
+                ) -> Result<(u64, usize)> {
+                    let len = source.peek_endian::<$ty>(endian)?;
+                    Ok((u64::from(len), <$ty>::SIZE))
+                }
+            }
+        )*
+    };
+}
+
+impl_length_prefix!(u8, u16, u32, u64);
+
+/// Unsigned LEB128 length prefix, as in protobuf and WebAssembly, the endianness is ignored
+pub enum Varint {}
+
+impl LengthPrefix for Varint {
+    fn peek_len<S: ByteSource + ?Sized>(source: &mut S, _endian: Endian) -> Result<(u64, usize)> {
+        varint::peek_leb128(source, 64, false)
+    }
+}
+
+impl<'a> ByteReader<'a> {
+    /// Reads a `P` length, in the reader's endianness, then that many bytes borrowed from the
+    /// buffer. The cursor is not advanced on failure
+    pub fn read_prefixed_bytes<P: LengthPrefix>(&mut self) -> Result<&'a [u8]> {
+        self.read_prefixed_bytes_endian::<P>(self.endian)
+    }
+
+    pub fn read_prefixed_bytes_endian<P: LengthPrefix>(
+        &mut self,
+        endian: Endian,
+    ) -> Result<&'a [u8]> {
+        let (prefix, len) = prefixed_len::<P, _>(self, endian)?;
+        let bytes = self.get_block_at(self.cursor + prefix, len)?;
+        self.cursor += prefix + len;
+        Ok(bytes)
+    }
+
+    /// Same as `read_prefixed_bytes` but the bytes must be UTF-8
+    pub fn read_prefixed_str<P: LengthPrefix>(&mut self) -> Result<&'a str> {
+        self.read_prefixed_str_endian::<P>(self.endian)
+    }
+
+    pub fn read_prefixed_str_endian<P: LengthPrefix>(&mut self, endian: Endian) -> Result<&'a str> {
+        let (prefix, len) = prefixed_len::<P, _>(self, endian)?;
+        let at = self.cursor + prefix;
+        let s = to_str(self.get_block_at(at, len)?, at)?;
+        self.cursor += prefix + len;
+        Ok(s)
+    }
+
+    pub fn max_prefixed_len(&self) -> usize {
+        self.max_prefixed_len
+    }
+
+    /// Makes `read_prefixed_bytes` and `read_prefixed_str` fail on lengths above `max`, they are
+    /// otherwise only limited by what is left in the buffer
+    pub fn set_max_prefixed_len(&mut self, max: usize) {
+        self.max_prefixed_len = max;
+    }
+}
+
+/// Returns the size of the prefix and the length it holds, checked against the source's
+/// `max_prefixed_len`, without advancing
+pub(crate) fn prefixed_len<P: LengthPrefix, S: ByteSource + ?Sized>(
+    source: &mut S,
+    endian: Endian,
+) -> Result<(usize, usize)> {
+    let at = source.get_position();
+    let (len, prefix) = P::peek_len(source, endian)?;
+    let max = source.max_prefixed_len();
+    match usize::try_from(len) {
+        Ok(len) if len <= max && len <= usize::MAX - prefix => Ok((prefix, len)),
+        _ => Err(ByteReaderError::PrefixTooLong { at, len, max }),
+    }
+}
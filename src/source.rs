@@ -0,0 +1,637 @@
+use std::ops::Range;
+
+use crate::{
+    BigEndian, ByteOrder, ByteReader, ByteReaderError, CStrMode, Endian, FromBytes, LengthPrefix,
+    LittleEndian, Result, Utf16Mode, half, prefixed, to_str, utf16, varint, zigzag_decode_32,
+    zigzag_decode_64,
+};
+
+/// Anything bytes can be read from with a tracked position, implemented by `ByteReader`,
+/// `StreamByteReader` and `&[u8]`.
+///
+/// Only the block level methods are required, the typed readers are built on top of them so
+/// parsers written against this trait work on every source.
+pub trait ByteSource {
+    /// Returns the next `n` bytes and advances past them
+    fn read_block(&mut self, n: usize) -> Result<&[u8]>;
+
+    /// Returns the next `n` bytes without advancing
+    fn peek_block(&mut self, n: usize) -> Result<&[u8]>;
+
+    fn advance(&mut self, n: usize) -> Result<()>;
+
+    fn get_position(&self) -> usize;
+
+    /// Byte order of the endian-less readers (`read_u32`, ...)
+    fn endian(&self) -> Endian {
+        Endian::Big
+    }
+
+    /// What `read_c_str` and `peek_c_str` do when the source ends before a NUL
+    fn c_str_mode(&self) -> CStrMode {
+        CStrMode::AllowEof
+    }
+
+    /// Longest length accepted by `read_prefixed_bytes` and `read_prefixed_str`
+    fn max_prefixed_len(&self) -> usize {
+        usize::MAX
+    }
+
+    /// What the UTF-16 readers do with unpaired surrogates
+    fn utf16_mode(&self) -> Utf16Mode {
+        Utf16Mode::Lossy
+    }
+
+    fn read<T: FromBytes, E: ByteOrder>(&mut self) -> Result<T> {
+        Ok(T::from_bytes::<E>(self.read_block(T::SIZE)?))
+    }
+
+    fn read_endian<T: FromBytes>(&mut self, endian: Endian) -> Result<T> {
+        match endian {
+            Endian::Big => self.read::<T, BigEndian>(),
+            Endian::Little => self.read::<T, LittleEndian>(),
+        }
+    }
+
+    fn peek<T: FromBytes, E: ByteOrder>(&mut self) -> Result<T> {
+        Ok(T::from_bytes::<E>(self.peek_block(T::SIZE)?))
+    }
+
+    fn peek_endian<T: FromBytes>(&mut self, endian: Endian) -> Result<T> {
+        match endian {
+            Endian::Big => self.peek::<T, BigEndian>(),
+            Endian::Little => self.peek::<T, LittleEndian>(),
+        }
+    }
+
+    fn read_c_str(&mut self) -> Result<String> {
+        let mode = self.c_str_mode();
+        read_c_str_within(self, usize::MAX, mode)
+    }
+
+    /// Same as `read_c_str` but fails unless a NUL is found within the next `limit` bytes,
+    /// whatever the `CStrMode`
+    fn read_c_str_max(&mut self, limit: usize) -> Result<String> {
+        read_c_str_within(self, limit, CStrMode::RequireNul)
+    }
+
+    fn peek_c_str(&mut self) -> Result<String> {
+        let mode = self.c_str_mode();
+        let (len, _) = c_str_len(self, usize::MAX, mode)?;
+        Ok(String::from_utf8_lossy(self.peek_block(len)?).into_owned())
+    }
+
+    /// Same as `read_c_str` but returns the bytes, without the NUL
+    fn read_c_str_bytes(&mut self) -> Result<&[u8]> {
+        let mode = self.c_str_mode();
+        let (len, terminated) = c_str_len(self, usize::MAX, mode)?;
+        Ok(&self.read_block(len + terminated as usize)?[..len])
+    }
+
+    /// Same as `read_c_str_bytes` but fails on invalid UTF-8 instead of replacing it, the source
+    /// is not advanced on failure
+    fn read_c_str_utf8(&mut self) -> Result<&str> {
+        let mode = self.c_str_mode();
+        let (len, terminated) = c_str_len(self, usize::MAX, mode)?;
+        read_utf8(self, 0..len, len + terminated as usize)
+    }
+
+    /// Reads `len` bytes of UTF-8, the source is not advanced on failure
+    fn read_str(&mut self, len: usize) -> Result<&str> {
+        read_utf8(self, 0..len, len)
+    }
+
+    /// Reads a `P` length, in the source's endianness, then that many bytes. The source is not
+    /// advanced on failure
+    fn read_prefixed_bytes<P: LengthPrefix>(&mut self) -> Result<&[u8]> {
+        let endian = self.endian();
+        self.read_prefixed_bytes_endian::<P>(endian)
+    }
+
+    fn read_prefixed_bytes_endian<P: LengthPrefix>(&mut self, endian: Endian) -> Result<&[u8]> {
+        let (prefix, len) = prefixed::prefixed_len::<P, _>(self, endian)?;
+        Ok(&self.read_block(prefix + len)?[prefix..])
+    }
+
+    /// Same as `read_prefixed_bytes` but the bytes must be UTF-8
+    fn read_prefixed_str<P: LengthPrefix>(&mut self) -> Result<&str> {
+        let endian = self.endian();
+        self.read_prefixed_str_endian::<P>(endian)
+    }
+
+    fn read_prefixed_str_endian<P: LengthPrefix>(&mut self, endian: Endian) -> Result<&str> {
+        let (prefix, len) = prefixed::prefixed_len::<P, _>(self, endian)?;
+        read_utf8(self, prefix..prefix + len, prefix + len)
+    }
+
+    fn read_uleb128(&mut self) -> Result<u64> {
+        varint::read_leb128(self, 64, false)
+    }
+
+    fn read_sleb128(&mut self) -> Result<i64> {
+        varint::read_leb128(self, 64, true).map(|n| n as i64)
+    }
+
+    /// Protobuf varint, same encoding as `read_uleb128` limited to 5 bytes
+    fn read_varint_u32(&mut self) -> Result<u32> {
+        varint::read_leb128(self, 32, false).map(|n| n as u32)
+    }
+
+    fn read_varint_u64(&mut self) -> Result<u64> {
+        varint::read_leb128(self, 64, false)
+    }
+
+    /// Zigzag encoded varint, protobuf `sint32`
+    fn read_zigzag_i32(&mut self) -> Result<i32> {
+        self.read_varint_u32().map(zigzag_decode_32)
+    }
+
+    /// Zigzag encoded varint, protobuf `sint64`
+    fn read_zigzag_i64(&mut self) -> Result<i64> {
+        self.read_varint_u64().map(zigzag_decode_64)
+    }
+
+    /// Reads `len` UTF-16 code units, so `2 * len` bytes, in the source's endianness. Unpaired
+    /// surrogates are handled according to the source's `Utf16Mode`
+    fn read_utf16(&mut self, len: usize) -> Result<String> {
+        let endian = self.endian();
+        utf16::read_utf16(self, 0, len, endian)
+    }
+
+    fn read_utf16_be(&mut self, len: usize) -> Result<String> {
+        utf16::read_utf16(self, 0, len, Endian::Big)
+    }
+
+    fn read_utf16_le(&mut self, len: usize) -> Result<String> {
+        utf16::read_utf16(self, 0, len, Endian::Little)
+    }
+
+    /// Reads `len` code units, a leading byte order mark included. The BOM picks the endianness
+    /// and is left out of the string, without one the source's endianness is used
+    fn read_utf16_auto(&mut self, len: usize) -> Result<String> {
+        utf16::read_utf16_auto(self, len)
+    }
+
+    /// Reads code units up to a NUL one in the source's endianness, the `CStrMode` decides what
+    /// happens when there is none. The source is not advanced on failure
+    fn read_c_wstr(&mut self) -> Result<String> {
+        let endian = self.endian();
+        utf16::read_c_wstr(self, endian)
+    }
+
+    fn read_c_wstr_be(&mut self) -> Result<String> {
+        utf16::read_c_wstr(self, Endian::Big)
+    }
+
+    fn read_c_wstr_le(&mut self) -> Result<String> {
+        utf16::read_c_wstr(self, Endian::Little)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        self.read::<u8, BigEndian>()
+    }
+
+    fn read_i8(&mut self) -> Result<i8> {
+        self.read::<i8, BigEndian>()
+    }
+
+    endian_methods! {
+        source read:
+        u16 => read_u16_be, read_u16_le, read_u16;
+        i16 => read_i16_be, read_i16_le, read_i16;
+        u32 => read_u32_be, read_u32_le, read_u32;
+        i32 => read_i32_be, read_i32_le, read_i32;
+        u64 => read_u64_be, read_u64_le, read_u64;
+        i64 => read_i64_be, read_i64_le, read_i64;
+        u128 => read_u128_be, read_u128_le, read_u128;
+        i128 => read_i128_be, read_i128_le, read_i128;
+        f32 => read_f32_be, read_f32_le, read_f32;
+        f64 => read_f64_be, read_f64_le, read_f64;
+    }
+
+    fn read_f16(&mut self) -> Result<f32> {
+        self.read_u16().map(half::f16_to_f32)
+    }
+
+    fn read_f16_be(&mut self) -> Result<f32> {
+        self.read_u16_be().map(half::f16_to_f32)
+    }
+
+    fn read_f16_le(&mut self) -> Result<f32> {
+        self.read_u16_le().map(half::f16_to_f32)
+    }
+
+    fn read_bf16(&mut self) -> Result<f32> {
+        self.read_u16().map(half::bf16_to_f32)
+    }
+
+    fn read_bf16_be(&mut self) -> Result<f32> {
+        self.read_u16_be().map(half::bf16_to_f32)
+    }
+
+    fn read_bf16_le(&mut self) -> Result<f32> {
+        self.read_u16_le().map(half::bf16_to_f32)
+    }
+
+    fn peek_u8(&mut self) -> Result<u8> {
+        self.peek::<u8, BigEndian>()
+    }
+
+    fn peek_i8(&mut self) -> Result<i8> {
+        self.peek::<i8, BigEndian>()
+    }
+
+    endian_methods! {
+        source peek:
+        u16 => peek_u16_be, peek_u16_le, peek_u16;
+        i16 => peek_i16_be, peek_i16_le, peek_i16;
+        u32 => peek_u32_be, peek_u32_le, peek_u32;
+        i32 => peek_i32_be, peek_i32_le, peek_i32;
+        u64 => peek_u64_be, peek_u64_le, peek_u64;
+        i64 => peek_i64_be, peek_i64_le, peek_i64;
+        u128 => peek_u128_be, peek_u128_le, peek_u128;
+        i128 => peek_i128_be, peek_i128_le, peek_i128;
+        f32 => peek_f32_be, peek_f32_le, peek_f32;
+        f64 => peek_f64_be, peek_f64_le, peek_f64;
+    }
+
+    fn peek_f16(&mut self) -> Result<f32> {
+        self.peek_u16().map(half::f16_to_f32)
+    }
+
+    fn peek_f16_be(&mut self) -> Result<f32> {
+        self.peek_u16_be().map(half::f16_to_f32)
+    }
+
+    fn peek_f16_le(&mut self) -> Result<f32> {
+        self.peek_u16_le().map(half::f16_to_f32)
+    }
+
+    fn peek_bf16(&mut self) -> Result<f32> {
+        self.peek_u16().map(half::bf16_to_f32)
+    }
+
+    fn peek_bf16_be(&mut self) -> Result<f32> {
+        self.peek_u16_be().map(half::bf16_to_f32)
+    }
+
+    fn peek_bf16_le(&mut self) -> Result<f32> {
+        self.peek_u16_le().map(half::bf16_to_f32)
+    }
+}
+
+/// A `ByteSource` that can jump to any position.
+pub trait SeekableByteSource: ByteSource {
+    fn set_position(&mut self, n: usize) -> Result<()>;
+
+    fn rewind(&mut self, n: usize) -> Result<()> {
+        let position = self.get_position();
+        if n > position {
+            return Err(ByteReaderError::RewindPastStart { position, n });
+        }
+
+        self.set_position(position - n)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.set_position(0)
+    }
+}
+
+fn read_c_str_within<S: ByteSource + ?Sized>(
+    source: &mut S,
+    limit: usize,
+    mode: CStrMode,
+) -> Result<String> {
+    let (len, terminated) = c_str_len(source, limit, mode)?;
+    let s = String::from_utf8_lossy(source.peek_block(len)?).into_owned();
+    source.advance(len + terminated as usize)?;
+    Ok(s)
+}
+
+/// Checks that `range` of the next `consumed` bytes is UTF-8 before advancing past them
+fn read_utf8<S: ByteSource + ?Sized>(
+    source: &mut S,
+    range: Range<usize>,
+    consumed: usize,
+) -> Result<&str> {
+    let at = source.get_position() + range.start;
+    // Checked on a peek first, as the source cannot always go back once advanced
+    to_str(&source.peek_block(consumed)?[range.clone()], at)?;
+    to_str(&source.read_block(consumed)?[range], at)
+}
+
+/// Length of the string up to the NUL or the end of the source, and whether the NUL was found
+pub(crate) fn c_str_len<S: ByteSource + ?Sized>(
+    source: &mut S,
+    limit: usize,
+    mode: CStrMode,
+) -> Result<(usize, bool)> {
+    source.peek_block(1)?;
+    let at = source.get_position();
+    let mut len = 0;
+    loop {
+        if len == limit {
+            return Err(ByteReaderError::UnterminatedCStr { at, searched: len });
+        }
+
+        match source.peek_block(len + 1) {
+            Ok(bytes) if bytes[len] == b'\0' => return Ok((len, true)),
+            Ok(_) => len += 1,
+            Err(ByteReaderError::UnexpectedEof { .. }) if mode == CStrMode::AllowEof => {
+                return Ok((len, false));
+            }
+            Err(ByteReaderError::UnexpectedEof { .. }) => {
+                return Err(ByteReaderError::UnterminatedCStr { at, searched: len });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+impl<'a> ByteSource for ByteReader<'a> {
+    fn read_block(&mut self, n: usize) -> Result<&[u8]> {
+        ByteReader::read_block(self, n)
+    }
+
+    fn peek_block(&mut self, n: usize) -> Result<&[u8]> {
+        ByteReader::peek_block(self, n)
+    }
+
+    fn advance(&mut self, n: usize) -> Result<()> {
+        ByteReader::advance(self, n)
+    }
+
+    fn get_position(&self) -> usize {
+        self.cursor
+    }
+
+    fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    fn c_str_mode(&self) -> CStrMode {
+        self.c_str_mode
+    }
+
+    fn max_prefixed_len(&self) -> usize {
+        self.max_prefixed_len
+    }
+
+    fn utf16_mode(&self) -> Utf16Mode {
+        self.utf16_mode
+    }
+}
+
+impl<'a> SeekableByteSource for ByteReader<'a> {
+    fn set_position(&mut self, n: usize) -> Result<()> {
+        ByteReader::set_position(self, n)
+    }
+}
+
+/// A bare slice is consumed from the front as it is read, it does not know where it started
+/// so its position is always 0 and errors report offsets relative to what is left.
+impl ByteSource for &[u8] {
+    fn read_block(&mut self, n: usize) -> Result<&[u8]> {
+        self.peek_block(n)?;
+        let (block, rest) = self.split_at(n);
+        *self = rest;
+        Ok(block)
+    }
+
+    fn peek_block(&mut self, n: usize) -> Result<&[u8]> {
+        self.get(..n).ok_or(ByteReaderError::UnexpectedEof {
+            at: 0,
+            needed: n,
+            available: self.len(),
+        })
+    }
+
+    fn advance(&mut self, n: usize) -> Result<()> {
+        self.read_block(n).map(|_| ())
+    }
+
+    fn get_position(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StreamByteReader, Varint};
+
+    /// Runs `check` on a `ByteReader`, a `StreamByteReader` and a bare slice over `bytes`
+    macro_rules! check_all_sources {
+        ($check:ident, $bytes:expr) => {{
+            let bytes: &[u8] = $bytes;
+            $check(&mut ByteReader::new(bytes));
+            $check(&mut StreamByteReader::new(bytes));
+            $check(&mut &bytes[..]);
+        }};
+    }
+
+    #[test]
+    fn varints() {
+        fn check<S: ByteSource>(source: &mut S) {
+            assert_eq!(source.read_uleb128().unwrap(), 624_485);
+            assert_eq!(source.read_sleb128().unwrap(), -123_456);
+            assert_eq!(source.read_varint_u32().unwrap(), u32::MAX);
+            assert_eq!(source.read_zigzag_i64().unwrap(), -2);
+            // 5th byte of a u32 varint holds bits past 32
+            assert!(matches!(
+                source.read_varint_u32(),
+                Err(ByteReaderError::VarintOverflow { .. })
+            ));
+            assert_eq!(source.read_u8().unwrap(), 0xff);
+        }
+
+        check_all_sources!(
+            check,
+            &[
+                0xe5, 0x8e, 0x26, 0xc0, 0xbb, 0x78, 0xff, 0xff, 0xff, 0xff, 0x0f, 0x03, 0xff, 0xff,
+                0xff, 0xff, 0x1f
+            ]
+        );
+    }
+
+    #[test]
+    fn strings() {
+        fn check<S: ByteSource>(source: &mut S) {
+            assert_eq!(source.read_c_str_bytes().unwrap(), b"ab");
+            assert_eq!(source.read_c_str_utf8().unwrap(), "cd");
+            assert_eq!(source.read_str(2).unwrap(), "ef");
+            assert!(matches!(
+                source.read_str(2),
+                Err(ByteReaderError::InvalidUtf8 { .. })
+            ));
+            assert!(matches!(
+                source.read_c_str_utf8(),
+                Err(ByteReaderError::InvalidUtf8 { .. })
+            ));
+            assert_eq!(source.read_u8().unwrap(), b'g');
+        }
+
+        check_all_sources!(check, b"ab\0cd\0efg\xff\0");
+    }
+
+    #[test]
+    fn prefixed() {
+        fn check<S: ByteSource>(source: &mut S) {
+            assert_eq!(source.read_prefixed_bytes::<u8>().unwrap(), b"abc");
+            assert_eq!(source.read_prefixed_str::<u16>().unwrap(), "hi");
+            assert_eq!(
+                source
+                    .read_prefixed_str_endian::<u32>(Endian::Little)
+                    .unwrap(),
+                "xy"
+            );
+            assert_eq!(source.read_prefixed_str::<Varint>().unwrap(), "z");
+            // Invalid UTF-8, then a length past the end, neither advances
+            assert!(matches!(
+                source.read_prefixed_str::<u8>(),
+                Err(ByteReaderError::InvalidUtf8 { .. })
+            ));
+            assert_eq!(source.read_prefixed_bytes::<u8>().unwrap(), b"\xff");
+            assert!(matches!(
+                source.read_prefixed_bytes::<u16>(),
+                Err(ByteReaderError::UnexpectedEof { .. })
+            ));
+            assert_eq!(source.read_u16().unwrap(), 0x00ff);
+        }
+
+        check_all_sources!(
+            check,
+            &[
+                3, b'a', b'b', b'c', 0, 2, b'h', b'i', 2, 0, 0, 0, b'x', b'y', 1, b'z', 1, 0xff, 0,
+                0xff, 1
+            ]
+        );
+    }
+
+    #[test]
+    fn prefixed_max_len() {
+        let bytes = [4, 1, 2, 3, 4];
+        let mut reader = StreamByteReader::new(&bytes[..]);
+        reader.set_max_prefixed_len(3);
+        assert!(matches!(
+            reader.read_prefixed_bytes::<u8>(),
+            Err(ByteReaderError::PrefixTooLong {
+                at: 0,
+                len: 4,
+                max: 3
+            })
+        ));
+        reader.set_max_prefixed_len(4);
+        assert_eq!(reader.read_prefixed_bytes::<u8>().unwrap(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn utf16() {
+        fn check<S: ByteSource>(source: &mut S) {
+            assert_eq!(source.read_utf16_le(2).unwrap(), "hi");
+            assert_eq!(source.read_utf16_auto(3).unwrap(), "ok");
+            assert_eq!(source.read_c_wstr_be().unwrap(), "a\u{fffd}");
+            assert_eq!(source.read_c_wstr_le().unwrap(), "z");
+            assert_eq!(source.read_u8().unwrap(), 0xff);
+        }
+
+        check_all_sources!(
+            check,
+            &[
+                b'h', 0, b'i', 0, 0xff, 0xfe, b'o', 0, b'k', 0, 0, b'a', 0xdc, 0x00, 0, 0, b'z', 0,
+                0xff
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_utf16_does_not_advance() {
+        let bytes = [b'a', 0, 0x00, 0xd8, 0, 0];
+        let mut reader = StreamByteReader::new(&bytes[..]);
+        reader.set_utf16_mode(Utf16Mode::Strict);
+        assert!(matches!(
+            reader.read_c_wstr_le(),
+            Err(ByteReaderError::UnpairedSurrogate { at: 2 })
+        ));
+        assert!(matches!(
+            reader.read_utf16_le(2),
+            Err(ByteReaderError::UnpairedSurrogate { at: 2 })
+        ));
+        assert_eq!(reader.read_u8().unwrap(), b'a');
+    }
+
+    #[test]
+    fn c_str_modes_at_non_zero_position() {
+        fn check<S: ByteSource>(source: &mut S, mode: CStrMode) {
+            source.advance(3).unwrap();
+            assert_eq!(source.read_c_str().unwrap(), "cd");
+            let at = source.get_position();
+            match mode {
+                CStrMode::AllowEof => {
+                    assert_eq!(source.peek_c_str().unwrap(), "ef");
+                    assert_eq!(source.read_c_str().unwrap(), "ef");
+                    assert!(source.read_u8().is_err());
+                }
+                CStrMode::RequireNul => {
+                    assert!(matches!(
+                        source.read_c_str(),
+                        Err(ByteReaderError::UnterminatedCStr { at: a, searched: 2 }) if a == at
+                    ));
+                    assert!(source.peek_c_str().is_err());
+                    assert!(source.read_c_str_bytes().is_err());
+                    assert_eq!(source.read_u8().unwrap(), b'e');
+                }
+            }
+        }
+
+        let bytes = b"ab\0cd\0ef";
+        for mode in [CStrMode::AllowEof, CStrMode::RequireNul] {
+            let mut reader = ByteReader::new(bytes);
+            reader.set_c_str_mode(mode);
+            check(&mut reader, mode);
+
+            let mut stream = StreamByteReader::new(&bytes[..]);
+            stream.set_c_str_mode(mode);
+            check(&mut stream, mode);
+        }
+
+        // A bare slice always allows the end of the buffer
+        check(&mut &bytes[..], CStrMode::AllowEof);
+    }
+
+    #[test]
+    fn c_str_nul_as_last_byte() {
+        fn check<S: ByteSource>(source: &mut S) {
+            source.advance(2).unwrap();
+            assert_eq!(source.read_c_str().unwrap(), "abc");
+            assert!(matches!(
+                source.read_c_str(),
+                Err(ByteReaderError::UnexpectedEof { .. })
+            ));
+        }
+
+        check_all_sources!(check, b"x\0abc\0");
+    }
+
+    #[test]
+    fn c_str_max_limit() {
+        fn check<S: ByteSource>(source: &mut S) {
+            source.advance(1).unwrap();
+            let at = source.get_position();
+            assert!(matches!(
+                source.read_c_str_max(4),
+                Err(ByteReaderError::UnterminatedCStr { at: a, searched: 4 }) if a == at
+            ));
+            assert_eq!(source.read_c_str_max(5).unwrap(), "abcd");
+            // Required even when the limit reaches past the end
+            assert!(matches!(
+                source.read_c_str_max(10),
+                Err(ByteReaderError::UnterminatedCStr { searched: 2, .. })
+            ));
+            assert_eq!(source.read_u8().unwrap(), b'e');
+        }
+
+        check_all_sources!(check, b"-abcd\0ef");
+    }
+}
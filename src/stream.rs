@@ -0,0 +1,333 @@
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+
+use crate::{ByteReaderError, ByteSource, CStrMode, Endian, Result, SeekableByteSource, Utf16Mode};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+// Caps how much is allocated ahead of the data actually arriving, so a bogus length read from
+// the stream fails with an EOF instead of an allocation failure
+const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Same as `ByteReader` but pulls the bytes from an `io::Read` source as they are needed,
+/// keeping only a small window of them in memory. The readers come from `ByteSource`, and from
+/// `SeekableByteSource` when the source is `Seek`.
+pub struct StreamByteReader<R> {
+    inner: R,
+    buffer: Vec<u8>,
+    // Index of the cursor within `buffer`
+    start: usize,
+    position: usize,
+    // Offset of `inner` that position 0 stands for, seeks go relative to it
+    base: u64,
+    endian: Endian,
+    c_str_mode: CStrMode,
+    max_prefixed_len: usize,
+    utf16_mode: Utf16Mode,
+}
+
+impl<R: Read> StreamByteReader<R> {
+    /// Creates a reader whose endian-less methods (`read_u32`, ...) read big endian.
+    ///
+    /// Positions count from where `inner` is when the reader is made. Seeking takes them as
+    /// offsets of `inner` from its start, so a `Seek` source that is not at its start should be
+    /// given to `seekable` instead.
+    pub fn new(inner: R) -> Self {
+        Self::with_endian(inner, Endian::Big)
+    }
+
+    pub fn with_endian(inner: R, endian: Endian) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            start: 0,
+            position: 0,
+            base: 0,
+            endian,
+            c_str_mode: CStrMode::AllowEof,
+            max_prefixed_len: usize::MAX,
+            utf16_mode: Utf16Mode::Lossy,
+        }
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    pub fn set_c_str_mode(&mut self, mode: CStrMode) {
+        self.c_str_mode = mode;
+    }
+
+    /// Makes `read_prefixed_bytes` and `read_prefixed_str` fail on lengths above `max`, which
+    /// also bounds how much they buffer
+    pub fn set_max_prefixed_len(&mut self, max: usize) {
+        self.max_prefixed_len = max;
+    }
+
+    pub fn set_utf16_mode(&mut self, mode: Utf16Mode) {
+        self.utf16_mode = mode;
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Bytes that were buffered but not read yet are lost
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.start += n;
+        self.position += n;
+    }
+
+    /// Makes sure at least `n` bytes are buffered past the cursor and returns them
+    fn fill(&mut self, n: usize) -> Result<&[u8]> {
+        while self.buffer.len() - self.start < n {
+            let available = self.buffer.len() - self.start;
+            let chunk = (n - available).clamp(CHUNK_SIZE, MAX_CHUNK_SIZE);
+            if self.fill_more(chunk)? == 0 {
+                return Err(ByteReaderError::UnexpectedEof {
+                    at: self.position,
+                    needed: n,
+                    available,
+                });
+            }
+        }
+
+        Ok(&self.buffer[self.start..self.start + n])
+    }
+
+    /// Reads up to `chunk` more bytes into the buffer, returns 0 at the end of the stream
+    fn fill_more(&mut self, chunk: usize) -> Result<usize> {
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+
+        let len = self.buffer.len();
+        self.buffer.resize(len + chunk, 0);
+        let read = loop {
+            match self.inner.read(&mut self.buffer[len..]) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buffer.truncate(len);
+                    return Err(e.into());
+                }
+            }
+        };
+
+        self.buffer.truncate(len + read);
+        Ok(read)
+    }
+}
+
+impl<R: Read> ByteSource for StreamByteReader<R> {
+    /// The block is only valid until the next read, copy it if you need to keep it
+    fn read_block(&mut self, n: usize) -> Result<&[u8]> {
+        self.fill(n)?;
+        let start = self.start;
+        self.consume(n);
+        Ok(&self.buffer[start..start + n])
+    }
+
+    fn peek_block(&mut self, n: usize) -> Result<&[u8]> {
+        self.fill(n)
+    }
+
+    fn advance(&mut self, n: usize) -> Result<()> {
+        let buffered = self.buffer.len() - self.start;
+        if n <= buffered {
+            self.consume(n);
+            return Ok(());
+        }
+
+        // The whole buffer is skipped, drop it so it is not taken as the bytes before `position`
+        let at = self.position;
+        self.position += buffered;
+        self.buffer.clear();
+        self.start = 0;
+        let remaining = (n - buffered) as u64;
+        let skipped = io::copy(&mut self.inner.by_ref().take(remaining), &mut io::sink())?;
+        self.position += skipped as usize;
+        if skipped < remaining {
+            return Err(ByteReaderError::UnexpectedEof {
+                at,
+                needed: n,
+                available: buffered + skipped as usize,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn get_position(&self) -> usize {
+        self.position
+    }
+
+    fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    fn c_str_mode(&self) -> CStrMode {
+        self.c_str_mode
+    }
+
+    fn max_prefixed_len(&self) -> usize {
+        self.max_prefixed_len
+    }
+
+    fn utf16_mode(&self) -> Utf16Mode {
+        self.utf16_mode
+    }
+}
+
+impl<R: Read + Seek> StreamByteReader<R> {
+    /// Same as `with_endian` but takes the current offset of `inner` as position 0, so seeking
+    /// stays relative to it wherever `inner` starts
+    pub fn seekable(mut inner: R, endian: Endian) -> Result<Self> {
+        let base = inner.stream_position()?;
+        Ok(Self {
+            base,
+            ..Self::with_endian(inner, endian)
+        })
+    }
+}
+
+impl<R: Read + Seek> SeekableByteSource for StreamByteReader<R> {
+    /// Seeks the underlying source unless `n` is already buffered. Unlike `ByteReader` positions
+    /// past the end are not rejected here, reading from them fails instead
+    fn set_position(&mut self, n: usize) -> Result<()> {
+        let buffer_start = self.position - self.start;
+        if (buffer_start..=buffer_start + self.buffer.len()).contains(&n) {
+            self.start = n - buffer_start;
+        } else {
+            self.inner.seek(SeekFrom::Start(self.base + n as u64))?;
+            self.buffer.clear();
+            self.start = 0;
+        }
+
+        self.position = n;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::ByteReader;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn seek_after_skipping_past_the_buffer() {
+        let data = data(20_000);
+        let mut reader = StreamByteReader::new(Cursor::new(&data));
+        assert_eq!(reader.read_u8().unwrap(), data[0]);
+        reader.advance(10_000).unwrap();
+        reader.set_position(2000).unwrap();
+        assert_eq!(reader.read_u8().unwrap(), data[2000]);
+        assert_eq!(reader.get_position(), 2001);
+    }
+
+    #[test]
+    fn seekable_counts_from_the_starting_offset() {
+        let data = data(3 * CHUNK_SIZE);
+        let mut cursor = Cursor::new(&data);
+        cursor.set_position(4);
+        let mut reader = StreamByteReader::seekable(cursor, Endian::Big).unwrap();
+        assert_eq!(reader.read_u8().unwrap(), data[4]);
+
+        // Still buffered
+        reader.set_position(2).unwrap();
+        assert_eq!(reader.read_u8().unwrap(), data[6]);
+
+        // Dropped from the buffer, so the source is sought
+        reader.advance(2 * CHUNK_SIZE).unwrap();
+        reader.set_position(1).unwrap();
+        assert_eq!(reader.read_u8().unwrap(), data[5]);
+        assert_eq!(reader.get_position(), 2);
+        reader.set_position(3 * CHUNK_SIZE - 5).unwrap();
+        assert_eq!(reader.read_u8().unwrap(), data[3 * CHUNK_SIZE - 1]);
+        assert!(reader.read_u8().is_err());
+    }
+
+    #[test]
+    fn interleaved_advance_seek_and_read_match_byte_reader() {
+        let data = data(50_000);
+        let mut expected = ByteReader::new(&data);
+        let mut reader = StreamByteReader::new(Cursor::new(&data));
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |max: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % max) as usize
+        };
+
+        for _ in 0..5000 {
+            match next(6) {
+                0 => {
+                    let n = next(3 * CHUNK_SIZE as u64);
+                    match expected.advance(n) {
+                        Ok(()) => reader.advance(n).unwrap(),
+                        Err(_) => {
+                            assert!(reader.advance(n).is_err());
+                            reader.set_position(expected.get_position()).unwrap();
+                        }
+                    }
+                }
+                1 => {
+                    let n = next(data.len() as u64 + 1);
+                    expected.set_position(n).unwrap();
+                    reader.set_position(n).unwrap();
+                }
+                2 => assert_eq!(reader.read_u8().ok(), expected.read_u8().ok()),
+                3 => assert_eq!(reader.read_u32_le().ok(), expected.read_u32_le().ok()),
+                4 => {
+                    let n = next(100);
+                    assert_eq!(reader.peek_block(n).ok(), expected.peek_block(n).ok());
+                }
+                _ => {
+                    let n = next(CHUNK_SIZE as u64 * 2);
+                    assert_eq!(reader.read_block(n).ok(), expected.read_block(n).ok());
+                }
+            }
+            assert_eq!(reader.get_position(), expected.get_position());
+        }
+    }
+
+    #[test]
+    fn advance_past_the_end_of_a_plain_reader() {
+        let data = data(100);
+        let mut reader = StreamByteReader::new(&data[..]);
+        reader.read_u8().unwrap();
+        assert!(matches!(
+            reader.advance(200),
+            Err(ByteReaderError::UnexpectedEof {
+                at: 1,
+                needed: 200,
+                available: 99
+            })
+        ));
+        assert!(reader.read_u8().is_err());
+    }
+
+    #[test]
+    fn reads_across_chunks() {
+        let data = data(3 * CHUNK_SIZE);
+        let mut reader = StreamByteReader::with_endian(&data[..], Endian::Little);
+        reader.advance(CHUNK_SIZE - 2).unwrap();
+        let expected = u32::from_le_bytes(data[CHUNK_SIZE - 2..CHUNK_SIZE + 2].try_into().unwrap());
+        assert_eq!(reader.read_u32().unwrap(), expected);
+        assert_eq!(
+            reader.read_block(2 * CHUNK_SIZE - 2).unwrap(),
+            &data[CHUNK_SIZE + 2..]
+        );
+        assert!(reader.read_u8().is_err());
+    }
+}
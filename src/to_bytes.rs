@@ -0,0 +1,73 @@
+use crate::{ByteOrder, Endian};
+
+/// A value that can be encoded into a fixed amount of bytes, the counterpart of `FromBytes`.
+///
+/// Not implemented for `usize` and `isize`, their size depends on the platform.
+///
+/// Implement it for your own types to write them with `ByteWriter::write`, composing the
+/// implementations of their fields.
+pub trait ToBytes {
+    /// Number of bytes produced by `to_bytes`.
+    const SIZE: usize;
+
+    /// Encodes the value into the first `SIZE` bytes of `bytes`, panics if fewer bytes are given.
+    fn to_bytes<E: ByteOrder>(&self, bytes: &mut [u8]);
+}
+
+macro_rules! impl_to_bytes {
+    ($($ty:ty),*) => {
+        $(
+            impl ToBytes for $ty {
+                const SIZE: usize = size_of::<$ty>();
+
+                fn to_bytes<E: ByteOrder>(&self, bytes: &mut [u8]) {
+                    let encoded = match E::ENDIAN {
+                        Endian::Big => self.to_be_bytes(),
+                        Endian::Little => self.to_le_bytes(),
+                    };
+                    bytes[..Self::SIZE].copy_from_slice(&encoded);
+                }
+            }
+        )*
+    };
+}
+
+impl_to_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl<T: ToBytes, const N: usize> ToBytes for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn to_bytes<E: ByteOrder>(&self, bytes: &mut [u8]) {
+        for (i, value) in self.iter().enumerate() {
+            value.to_bytes::<E>(&mut bytes[i * T::SIZE..]);
+        }
+    }
+}
+
+macro_rules! impl_to_bytes_tuple {
+    ($($name:ident $value:ident),+) => {
+        impl<$($name: ToBytes),+> ToBytes for ($($name,)+) {
+            const SIZE: usize = 0 $(+ $name::SIZE)+;
+
+            // The offset is bumped once more after the last field
+            #[allow(unused_assignments)]
+            fn to_bytes<E: ByteOrder>(&self, bytes: &mut [u8]) {
+                let ($($value,)+) = self;
+                let mut offset = 0;
+                $(
+                    $value.to_bytes::<E>(&mut bytes[offset..]);
+                    offset += $name::SIZE;
+                )+
+            }
+        }
+    };
+}
+
+impl_to_bytes_tuple!(T1 t1);
+impl_to_bytes_tuple!(T1 t1, T2 t2);
+impl_to_bytes_tuple!(T1 t1, T2 t2, T3 t3);
+impl_to_bytes_tuple!(T1 t1, T2 t2, T3 t3, T4 t4);
+impl_to_bytes_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5);
+impl_to_bytes_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6);
+impl_to_bytes_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7);
+impl_to_bytes_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7, T8 t8);
@@ -0,0 +1,117 @@
+use crate::{ByteReader, ByteReaderError, ByteSource, CStrMode, Endian, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf16Mode {
+    /// Fail with `ByteReaderError::UnpairedSurrogate`
+    Strict,
+    /// Replace unpaired surrogates with U+FFFD
+    #[default]
+    Lossy,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn utf16_mode(&self) -> Utf16Mode {
+        self.utf16_mode
+    }
+
+    pub fn set_utf16_mode(&mut self, mode: Utf16Mode) {
+        self.utf16_mode = mode;
+    }
+}
+
+/// Reads `len` code units after skipping `skip` bytes, the source is only advanced on success
+pub(crate) fn read_utf16<S: ByteSource + ?Sized>(
+    source: &mut S,
+    skip: usize,
+    len: usize,
+    endian: Endian,
+) -> Result<String> {
+    let at = source.get_position() + skip;
+    let mode = source.utf16_mode();
+    let n = skip.saturating_add(len.saturating_mul(2));
+    let s = decode_utf16(&source.peek_block(n)?[skip..], at, endian, mode)?;
+    source.advance(n)?;
+    Ok(s)
+}
+
+pub(crate) fn read_utf16_auto<S: ByteSource + ?Sized>(
+    source: &mut S,
+    len: usize,
+) -> Result<String> {
+    let bom = match source.peek_block(2) {
+        Ok([0xff, 0xfe]) if len > 0 => Some(Endian::Little),
+        Ok([0xfe, 0xff]) if len > 0 => Some(Endian::Big),
+        _ => None,
+    };
+
+    match bom {
+        Some(endian) => read_utf16(source, 2, len - 1, endian),
+        None => {
+            let endian = source.endian();
+            read_utf16(source, 0, len, endian)
+        }
+    }
+}
+
+pub(crate) fn read_c_wstr<S: ByteSource + ?Sized>(
+    source: &mut S,
+    endian: Endian,
+) -> Result<String> {
+    source.peek_block(2)?;
+    let at = source.get_position();
+    let mode = source.c_str_mode();
+    // A trailing odd byte cannot be part of the string, so the search goes a whole unit at a time
+    let mut units = 0;
+    let terminated = loop {
+        match source.peek_block(units * 2 + 2) {
+            Ok(bytes) if bytes[units * 2..] == [0, 0] => break true,
+            Ok(_) => units += 1,
+            Err(ByteReaderError::UnexpectedEof { .. }) if mode == CStrMode::AllowEof => {
+                break false;
+            }
+            Err(ByteReaderError::UnexpectedEof { .. }) => {
+                return Err(ByteReaderError::UnterminatedCStr {
+                    at,
+                    searched: units * 2,
+                });
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    let utf16_mode = source.utf16_mode();
+    let s = decode_utf16(source.peek_block(units * 2)?, at, endian, utf16_mode)?;
+    source.advance(units * 2 + if terminated { 2 } else { 0 })?;
+    Ok(s)
+}
+
+/// `at` is the offset of `bytes` in the buffer, used to locate unpaired surrogates
+fn decode_utf16(bytes: &[u8], at: usize, endian: Endian, mode: Utf16Mode) -> Result<String> {
+    let units = bytes.chunks_exact(2).map(|unit| match endian {
+        Endian::Big => u16::from_be_bytes([unit[0], unit[1]]),
+        Endian::Little => u16::from_le_bytes([unit[0], unit[1]]),
+    });
+
+    let mut s = String::with_capacity(bytes.len() / 2);
+    // Code units decoded so far, to report where an unpaired surrogate is
+    let mut decoded = 0;
+    for c in char::decode_utf16(units) {
+        match (c, mode) {
+            (Ok(c), _) => {
+                s.push(c);
+                decoded += c.len_utf16();
+            }
+            (Err(_), Utf16Mode::Lossy) => {
+                s.push(char::REPLACEMENT_CHARACTER);
+                decoded += 1;
+            }
+            (Err(_), Utf16Mode::Strict) => {
+                return Err(ByteReaderError::UnpairedSurrogate {
+                    at: at + decoded * 2,
+                });
+            }
+        }
+    }
+
+    Ok(s)
+}
@@ -0,0 +1,180 @@
+use crate::{ByteReaderError, ByteSource, Result};
+
+pub fn zigzag_decode_32(n: u32) -> i32 {
+    (n >> 1) as i32 ^ -((n & 1) as i32)
+}
+
+pub fn zigzag_decode_64(n: u64) -> i64 {
+    (n >> 1) as i64 ^ -((n & 1) as i64)
+}
+
+/// Decodes a LEB128 value into `bits` bits without advancing, returns it with its length in bytes
+pub(crate) fn peek_leb128<S: ByteSource + ?Sized>(
+    source: &mut S,
+    bits: u32,
+    signed: bool,
+) -> Result<(u64, usize)> {
+    let at = source.get_position();
+    let mut len = 0;
+    let mut value = 0u64;
+    let mut shift = 0;
+
+    loop {
+        let byte = source.peek_block(len + 1)?[len];
+        len += 1;
+
+        let payload = (byte & 0x7f) as u64;
+        let remaining = bits - shift;
+        if remaining <= 7 {
+            // Last possible byte, bits past the target width must be zero, or copies of the
+            // sign bit for signed values, and no continuation is allowed
+            let excess = payload >> remaining;
+            let expected = match signed && (payload >> (remaining - 1)) & 1 == 1 {
+                true => 0x7f >> remaining,
+                false => 0,
+            };
+            if excess != expected || byte & 0x80 != 0 {
+                return Err(ByteReaderError::VarintOverflow { at });
+            }
+        }
+
+        value |= payload << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if signed && shift < bits && byte & 0x40 != 0 {
+                value |= u64::MAX << shift;
+            }
+            return Ok((value, len));
+        }
+    }
+}
+
+/// Same as `peek_leb128` but advances past the value, the source is only advanced on success
+pub(crate) fn read_leb128<S: ByteSource + ?Sized>(
+    source: &mut S,
+    bits: u32,
+    signed: bool,
+) -> Result<u64> {
+    let (value, len) = peek_leb128(source, bits, signed)?;
+    source.advance(len)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ByteReader;
+
+    #[test]
+    fn uleb128_u64_max() {
+        let bytes = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        let mut reader = ByteReader::new(&bytes);
+        assert_eq!(reader.read_uleb128().unwrap(), u64::MAX);
+        assert_eq!(reader.get_position(), 10);
+    }
+
+    #[test]
+    fn uleb128_overflow() {
+        // 11 bytes, one continuation too many
+        let bytes = [
+            0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00,
+        ];
+        let mut reader = ByteReader::new(&bytes);
+        assert!(matches!(
+            reader.read_uleb128(),
+            Err(ByteReaderError::VarintOverflow { at: 0 })
+        ));
+        assert_eq!(reader.get_position(), 0);
+
+        // 10 bytes but the last one carries bits past 64
+        let bytes = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02];
+        assert!(matches!(
+            ByteReader::new(&bytes).read_uleb128(),
+            Err(ByteReaderError::VarintOverflow { at: 0 })
+        ));
+    }
+
+    #[test]
+    fn uleb128_small_values() {
+        let mut reader = ByteReader::new(&[0x00, 0x7f, 0x80, 0x01, 0xe5, 0x8e, 0x26]);
+        assert_eq!(reader.read_uleb128().unwrap(), 0);
+        assert_eq!(reader.read_uleb128().unwrap(), 127);
+        assert_eq!(reader.read_uleb128().unwrap(), 128);
+        assert_eq!(reader.read_uleb128().unwrap(), 624_485);
+    }
+
+    #[test]
+    fn sleb128_values() {
+        let mut reader = ByteReader::new(&[0x02, 0x7e, 0xff, 0x00, 0x81, 0x7f, 0xc0, 0xbb, 0x78]);
+        assert_eq!(reader.read_sleb128().unwrap(), 2);
+        assert_eq!(reader.read_sleb128().unwrap(), -2);
+        assert_eq!(reader.read_sleb128().unwrap(), 127);
+        assert_eq!(reader.read_sleb128().unwrap(), -127);
+        assert_eq!(reader.read_sleb128().unwrap(), -123_456);
+    }
+
+    #[test]
+    fn sleb128_i64_min_and_max() {
+        let min = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7f];
+        assert_eq!(ByteReader::new(&min).read_sleb128().unwrap(), i64::MIN);
+
+        let max = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+        assert_eq!(ByteReader::new(&max).read_sleb128().unwrap(), i64::MAX);
+
+        // The last byte must only hold copies of the sign bit
+        let bad = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7e];
+        assert!(matches!(
+            ByteReader::new(&bad).read_sleb128(),
+            Err(ByteReaderError::VarintOverflow { at: 0 })
+        ));
+    }
+
+    #[test]
+    fn varint_u32_limits() {
+        let max = [0xff, 0xff, 0xff, 0xff, 0x0f];
+        assert_eq!(ByteReader::new(&max).read_varint_u32().unwrap(), u32::MAX);
+
+        // Fifth byte holds bits past 32
+        let too_big = [0xff, 0xff, 0xff, 0xff, 0x1f];
+        assert!(matches!(
+            ByteReader::new(&too_big).read_varint_u32(),
+            Err(ByteReaderError::VarintOverflow { at: 0 })
+        ));
+
+        // Fifth byte has a continuation
+        let too_long = [0x80, 0x80, 0x80, 0x80, 0x80, 0x00];
+        assert!(matches!(
+            ByteReader::new(&too_long).read_varint_u32(),
+            Err(ByteReaderError::VarintOverflow { at: 0 })
+        ));
+    }
+
+    #[test]
+    fn truncated_varint_reports_start() {
+        let mut reader = ByteReader::new(&[0x01, 0x80, 0x80]);
+        reader.advance(1).unwrap();
+        assert!(matches!(
+            reader.read_uleb128(),
+            Err(ByteReaderError::UnexpectedEof {
+                at: 1,
+                needed: 3,
+                available: 2
+            })
+        ));
+        assert_eq!(reader.get_position(), 1);
+    }
+
+    #[test]
+    fn zigzag() {
+        assert_eq!(zigzag_decode_32(0), 0);
+        assert_eq!(zigzag_decode_32(1), -1);
+        assert_eq!(zigzag_decode_32(2), 1);
+        assert_eq!(zigzag_decode_32(u32::MAX - 1), i32::MAX);
+        assert_eq!(zigzag_decode_32(u32::MAX), i32::MIN);
+        assert_eq!(zigzag_decode_64(u64::MAX), i64::MIN);
+
+        let mut reader = ByteReader::new(&[0x03, 0xff, 0xff, 0xff, 0xff, 0x0f]);
+        assert_eq!(reader.read_zigzag_i32().unwrap(), -2);
+        assert_eq!(reader.read_zigzag_i64().unwrap(), i32::MIN as i64);
+    }
+}
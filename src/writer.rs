@@ -0,0 +1,492 @@
+use std::{
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{BigEndian, ByteOrder, ByteReaderError, Endian, LittleEndian, Result, ToBytes};
+
+// Tells writers apart so a placeholder cannot be filled in a writer it was not reserved in
+static NEXT_WRITER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A slot reserved by `ByteWriter::reserve`, to be filled once its value is known, typically a
+/// length or an offset of data written after it.
+#[must_use = "a reserved placeholder must be filled with `ByteWriter::fill`"]
+#[derive(Debug)]
+pub struct Placeholder<T> {
+    writer: u64,
+    id: u64,
+    position: usize,
+    endian: Endian,
+    kind: PhantomData<T>,
+}
+
+impl<T> Placeholder<T> {
+    /// Where the slot starts
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// Builds a buffer with a tracked cursor, the counterpart of `ByteReader`.
+///
+/// Writing past the end grows the buffer, writing before it overwrites what is already there,
+/// which is how fields are patched after moving back with `set_position`.
+#[derive(Debug, Clone)]
+pub struct ByteWriter {
+    id: u64,
+    cursor: usize,
+    buffer: Vec<u8>,
+    endian: Endian,
+    pad: u8,
+    next_placeholder: u64,
+    // Ids and positions of the placeholders that were not filled yet
+    placeholders: Vec<(u64, usize)>,
+}
+
+impl Default for ByteWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ByteWriter {
+    /// Creates a writer whose endian-less methods (`write_u32`, ...) write big endian
+    pub fn new() -> Self {
+        Self::with_endian(Endian::Big)
+    }
+
+    pub fn with_endian(endian: Endian) -> Self {
+        Self {
+            id: NEXT_WRITER_ID.fetch_add(1, Ordering::Relaxed),
+            cursor: 0,
+            buffer: Vec::new(),
+            endian,
+            pad: 0,
+            next_placeholder: 0,
+            placeholders: Vec::new(),
+        }
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    /// Byte used by `align` to fill the gap, 0 by default
+    pub fn set_pad_byte(&mut self, pad: u8) {
+        self.pad = pad;
+    }
+
+    /// Fails without writing anything if `s` contains a NUL, it would be read back cut short
+    pub fn write_c_str(&mut self, s: &str) -> Result<()> {
+        if let Some(index) = s.bytes().position(|b| b == b'\0') {
+            return Err(ByteReaderError::InteriorNul {
+                at: self.cursor + index,
+            });
+        }
+
+        self.write_block(s.as_bytes());
+        self.write_u8(b'\0');
+        Ok(())
+    }
+
+    pub fn write<T: ToBytes, E: ByteOrder>(&mut self, value: T) {
+        self.write_ref::<T, E>(&value);
+    }
+
+    pub(crate) fn write_ref<T: ToBytes, E: ByteOrder>(&mut self, value: &T) {
+        let end = self.grow(T::SIZE);
+        value.to_bytes::<E>(&mut self.buffer[self.cursor..end]);
+        self.cursor = end;
+    }
+
+    pub fn write_endian<T: ToBytes>(&mut self, value: T, endian: Endian) {
+        match endian {
+            Endian::Big => self.write::<T, BigEndian>(value),
+            Endian::Little => self.write::<T, LittleEndian>(value),
+        }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.write::<u8, BigEndian>(value);
+    }
+
+    pub fn write_i8(&mut self, value: i8) {
+        self.write::<i8, BigEndian>(value);
+    }
+
+    endian_methods! {
+        write:
+        u16 => write_u16_be, write_u16_le, write_u16;
+        i16 => write_i16_be, write_i16_le, write_i16;
+        u32 => write_u32_be, write_u32_le, write_u32;
+        i32 => write_i32_be, write_i32_le, write_i32;
+        u64 => write_u64_be, write_u64_le, write_u64;
+        i64 => write_i64_be, write_i64_le, write_i64;
+        u128 => write_u128_be, write_u128_le, write_u128;
+        i128 => write_i128_be, write_i128_le, write_i128;
+        f32 => write_f32_be, write_f32_le, write_f32;
+        f64 => write_f64_be, write_f64_le, write_f64;
+    }
+
+    pub fn write_block(&mut self, bytes: &[u8]) {
+        let end = self.grow(bytes.len());
+        self.buffer[self.cursor..end].copy_from_slice(bytes);
+        self.cursor = end;
+    }
+
+    /// Writes pad bytes until the position is a multiple of `n`, fails if `n` is 0 like
+    /// `ByteReader::align`
+    pub fn align(&mut self, n: usize) -> Result<()> {
+        if n == 0 {
+            return Err(ByteReaderError::InvalidAlignment { align: n });
+        }
+
+        let padding = self.cursor.next_multiple_of(n) - self.cursor;
+        let end = self.grow(padding);
+        self.buffer[self.cursor..end].fill(self.pad);
+        self.cursor = end;
+        Ok(())
+    }
+
+    /// Writes a zeroed slot for a `T` and returns a placeholder to fill it with `fill` later
+    pub fn reserve<T: ToBytes, E: ByteOrder>(&mut self) -> Placeholder<T> {
+        let position = self.cursor;
+        let end = self.grow(T::SIZE);
+        self.buffer[position..end].fill(0);
+        self.cursor = end;
+        let id = self.next_placeholder;
+        self.next_placeholder += 1;
+        self.placeholders.push((id, position));
+        Placeholder {
+            writer: self.id,
+            id,
+            position,
+            endian: E::ENDIAN,
+            kind: PhantomData,
+        }
+    }
+
+    pub fn reserve_endian<T: ToBytes>(&mut self, endian: Endian) -> Placeholder<T> {
+        match endian {
+            Endian::Big => self.reserve::<T, BigEndian>(),
+            Endian::Little => self.reserve::<T, LittleEndian>(),
+        }
+    }
+
+    endian_methods! {
+        reserve:
+        u16 => reserve_u16_be, reserve_u16_le, reserve_u16;
+        u32 => reserve_u32_be, reserve_u32_le, reserve_u32;
+        u64 => reserve_u64_be, reserve_u64_le, reserve_u64;
+    }
+
+    /// Writes `value` into the slot of `placeholder` with the endianness it was reserved with,
+    /// the cursor is left where it is.
+    ///
+    /// # Panics
+    ///
+    /// If `placeholder` was reserved by another writer.
+    pub fn fill<T: ToBytes>(&mut self, placeholder: Placeholder<T>, value: T) {
+        assert_eq!(
+            placeholder.writer, self.id,
+            "placeholder was reserved by another ByteWriter"
+        );
+        let cursor = self.cursor;
+        self.cursor = placeholder.position;
+        self.write_endian(value, placeholder.endian);
+        self.cursor = cursor;
+        self.placeholders.retain(|&(id, _)| id != placeholder.id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.cursor
+    }
+
+    /// Moves the cursor anywhere within the written bytes, or to the end to keep appending
+    pub fn set_position(&mut self, n: usize) -> Result<()> {
+        if n > self.buffer.len() {
+            return Err(ByteReaderError::OutOfBounds {
+                position: n,
+                len: self.buffer.len(),
+            });
+        }
+
+        self.cursor = n;
+        Ok(())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Fails if a placeholder was reserved but never filled
+    pub fn into_inner(self) -> Result<Vec<u8>> {
+        if let Some(position) = self.placeholders.iter().map(|&(_, p)| p).min() {
+            return Err(ByteReaderError::UnfilledPlaceholder { position });
+        }
+
+        Ok(self.buffer)
+    }
+
+    /// Makes room for `n` bytes at the cursor and returns where they end
+    fn grow(&mut self, n: usize) -> usize {
+        let end = self.cursor + n;
+        if end > self.buffer.len() {
+            self.buffer.resize(end, 0);
+        }
+
+        end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ByteReader, ByteSource};
+
+    /// Writes every value with its writer, then reads them all back with the matching readers
+    macro_rules! round_trip {
+        ($endian:expr; $($write:ident => $read:ident: $value:expr;)*) => {{
+            let mut writer = ByteWriter::with_endian($endian);
+            $(writer.$write($value);)*
+            let bytes = writer.into_inner().unwrap();
+            let mut reader = ByteReader::with_endian(&bytes, $endian);
+            $(assert_eq!(reader.$read().unwrap(), $value, stringify!($read));)*
+            assert_eq!(reader.get_position(), bytes.len());
+            bytes
+        }};
+    }
+
+    #[test]
+    fn explicit_endian_round_trip() {
+        round_trip! {
+            Endian::Big;
+            write_u8 => read_u8: 0xab;
+            write_i8 => read_i8: -2;
+            write_u16_be => read_u16_be: 0x1234;
+            write_u16_le => read_u16_le: 0x1234;
+            write_i16_be => read_i16_be: -0x1234;
+            write_i16_le => read_i16_le: -0x1234;
+            write_u32_be => read_u32_be: 0x1234_5678;
+            write_u32_le => read_u32_le: 0x1234_5678;
+            write_i32_be => read_i32_be: i32::MIN;
+            write_i32_le => read_i32_le: i32::MIN;
+            write_u64_be => read_u64_be: 0x0123_4567_89ab_cdef;
+            write_u64_le => read_u64_le: 0x0123_4567_89ab_cdef;
+            write_i64_be => read_i64_be: -0x0123_4567_89ab_cdef;
+            write_i64_le => read_i64_le: -0x0123_4567_89ab_cdef;
+            write_u128_be => read_u128_be: u128::MAX - 0xff;
+            write_u128_le => read_u128_le: u128::MAX - 0xff;
+            write_i128_be => read_i128_be: i128::MIN + 1;
+            write_i128_le => read_i128_le: i128::MIN + 1;
+            write_f32_be => read_f32_be: -1.5;
+            write_f32_le => read_f32_le: f32::MAX;
+            write_f64_be => read_f64_be: std::f64::consts::PI;
+            write_f64_le => read_f64_le: f64::MIN_POSITIVE;
+        };
+    }
+
+    #[test]
+    fn default_endian_round_trip() {
+        for endian in [Endian::Big, Endian::Little] {
+            round_trip! {
+                endian;
+                write_u16 => read_u16: 0x1234;
+                write_i16 => read_i16: -0x1234;
+                write_u32 => read_u32: 0x1234_5678;
+                write_i32 => read_i32: -0x1234_5678;
+                write_u64 => read_u64: 0x0123_4567_89ab_cdef;
+                write_i64 => read_i64: i64::MIN;
+                write_u128 => read_u128: 1 << 100;
+                write_i128 => read_i128: -(1 << 100);
+                write_f32 => read_f32: 0.1;
+                write_f64 => read_f64: -0.1;
+            };
+        }
+    }
+
+    #[test]
+    fn byte_layout() {
+        let bytes = round_trip! {
+            Endian::Little;
+            write_u16_be => read_u16_be: 0x0102;
+            write_u16_le => read_u16_le: 0x0102;
+            write_u32 => read_u32: 0x0304_0506;
+        };
+        assert_eq!(bytes, [1, 2, 2, 1, 6, 5, 4, 3]);
+    }
+
+    #[test]
+    fn generic_round_trip() {
+        let mut writer = ByteWriter::new();
+        writer.write::<(u8, [u16; 2], i32), LittleEndian>((1, [2, 3], -4));
+        writer.write_endian(5u64, Endian::Big);
+        let bytes = writer.into_inner().unwrap();
+        let mut reader = ByteReader::new(&bytes);
+        assert_eq!(
+            reader.read::<(u8, [u16; 2], i32), LittleEndian>().unwrap(),
+            (1, [2, 3], -4)
+        );
+        assert_eq!(reader.read_endian::<u64>(Endian::Big).unwrap(), 5);
+    }
+
+    #[test]
+    fn c_str_and_block_round_trip() {
+        let mut writer = ByteWriter::new();
+        writer.write_c_str("hello").unwrap();
+        writer.write_c_str("").unwrap();
+        writer.write_block(b"raw");
+        writer.write_c_str("wörld").unwrap();
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(&bytes[..7], b"hello\0\0");
+
+        let mut reader = ByteReader::new(&bytes);
+        assert_eq!(reader.read_c_str().unwrap(), "hello");
+        assert_eq!(reader.read_c_str_utf8().unwrap(), "");
+        assert_eq!(reader.read_block(3).unwrap(), b"raw");
+        assert_eq!(reader.read_c_str_utf8().unwrap(), "wörld");
+        assert_eq!(reader.get_position(), bytes.len());
+    }
+
+    #[test]
+    fn c_str_with_interior_nul() {
+        let mut writer = ByteWriter::new();
+        writer.write_u8(1);
+        assert!(matches!(
+            writer.write_c_str("a\0b"),
+            Err(ByteReaderError::InteriorNul { at: 2 })
+        ));
+        assert_eq!(writer.as_bytes(), [1]);
+    }
+
+    #[test]
+    fn align_with_pad_byte() {
+        let mut writer = ByteWriter::new();
+        writer.set_pad_byte(0xcc);
+        writer.write_u8(1);
+        writer.align(4).unwrap();
+        writer.write_u16_be(2);
+        writer.align(4).unwrap();
+        // Already aligned, nothing is written
+        writer.align(4).unwrap();
+        writer.write_u8(3);
+        writer.align(8).unwrap();
+        assert!(matches!(
+            writer.align(0),
+            Err(ByteReaderError::InvalidAlignment { align: 0 })
+        ));
+
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(
+            bytes,
+            [
+                1, 0xcc, 0xcc, 0xcc, 0, 2, 0xcc, 0xcc, 3, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc
+            ]
+        );
+
+        let mut reader = ByteReader::new(&bytes);
+        assert_eq!(reader.read_u8().unwrap(), 1);
+        reader.align(4).unwrap();
+        assert_eq!(reader.read_u16_be().unwrap(), 2);
+        reader.align(4).unwrap();
+        assert_eq!(reader.read_u8().unwrap(), 3);
+        reader.align(8).unwrap();
+        assert_eq!(reader.get_position(), bytes.len());
+    }
+
+    #[test]
+    fn patch_with_set_position() {
+        let mut writer = ByteWriter::new();
+        writer.write_u32_le(0);
+        writer.write_block(b"payload");
+        let end = writer.get_position();
+        writer.set_position(0).unwrap();
+        writer.write_u32_le(7);
+        assert_eq!(writer.get_position(), 4);
+        writer.set_position(end).unwrap();
+        writer.write_u8(0xff);
+        assert!(matches!(
+            writer.set_position(100),
+            Err(ByteReaderError::OutOfBounds {
+                position: 100,
+                len: 12
+            })
+        ));
+
+        let bytes = writer.into_inner().unwrap();
+        let mut reader = ByteReader::new(&bytes);
+        let len = reader.read_u32_le().unwrap() as usize;
+        assert_eq!(reader.read_block(len).unwrap(), b"payload");
+        assert_eq!(reader.read_u8().unwrap(), 0xff);
+    }
+
+    #[test]
+    fn length_filled_after_the_payload() {
+        for endian in [Endian::Big, Endian::Little] {
+            let mut writer = ByteWriter::with_endian(endian);
+            writer.write_u8(0xaa);
+            let len = writer.reserve_u32();
+            let start = writer.get_position();
+            writer.write_block(b"chunk data");
+            let end = writer.get_position();
+            writer.fill(len, (end - start) as u32);
+            assert_eq!(writer.get_position(), end);
+            writer.write_u8(0xbb);
+
+            let bytes = writer.into_inner().unwrap();
+            let mut reader = ByteReader::with_endian(&bytes, endian);
+            assert_eq!(reader.read_u8().unwrap(), 0xaa);
+            let len = reader.read_u32().unwrap() as usize;
+            assert_eq!(reader.read_block(len).unwrap(), b"chunk data");
+            assert_eq!(reader.read_u8().unwrap(), 0xbb);
+        }
+    }
+
+    #[test]
+    fn unfilled_placeholder() {
+        let mut writer = ByteWriter::new();
+        writer.write_u8(1);
+        let first = writer.reserve_u16_le();
+        let _second = writer.reserve_u64_be();
+        writer.fill(first, 2);
+        assert!(matches!(
+            writer.into_inner(),
+            Err(ByteReaderError::UnfilledPlaceholder { position: 3 })
+        ));
+    }
+
+    #[test]
+    fn placeholders_at_the_same_position_are_tracked_apart() {
+        let mut writer = ByteWriter::new();
+        let _first = writer.reserve_u32();
+        writer.set_position(0).unwrap();
+        let second = writer.reserve_u32();
+        writer.fill(second, 1);
+        assert!(matches!(
+            writer.into_inner(),
+            Err(ByteReaderError::UnfilledPlaceholder { position: 0 })
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "placeholder was reserved by another ByteWriter")]
+    fn fill_with_a_placeholder_from_another_writer() {
+        let mut first = ByteWriter::new();
+        let mut second = ByteWriter::default();
+        second.write_u32(0);
+        let placeholder = first.reserve_u32();
+        second.fill(placeholder, 1);
+    }
+}